@@ -10,7 +10,7 @@ fn main() {
     let mut repl = repl!(String);
 
     // Inject our custom commands.
-    repl.data.with_cmdtree_builder(custom_cmds()).unwrap();
+    repl.data.with_cmdtree_builder_fn(custom_cmds).unwrap();
 
     // Create the persistent data.
     let mut app_data = String::new();
@@ -33,6 +33,50 @@
 //! to control how changes to `app_data` need to occur, especially by ensuring mutable access is
 //! harding to achieve.
 //!
+//! Mutable mode takes options. `:mut persist` keeps the input once it has run, the kept inputs are
+//! listed with `:mut ls` and can be run again with `:mut replay 0`, where `0` is the index in the
+//! list. If the app data implements `Clone`, transactional blocks can be enabled with
+//! [`ReplData::with_transactional_mut`](crate::repl::ReplData::with_transactional_mut).
+//! `:mut tx` then snapshots `app_data` before the block runs, and restores it if the block fails or
+//! panics, such that `app_data` is not left partially mutated. Options can be combined, for example
+//! `:mut persist tx`.
+//!
+//! ## Loading Files
+//! The `load` command reads a rust source file and evaluates it as if it was typed into the REPL,
+//! unlike static files which are compiled as separate modules. Crates and items are added first,
+//! then the statements are evaluated in groups, a group ending at each expression without a
+//! trailing semi-colon. The inputs are added to the current module, and loading stops at the first
+//! input that fails, reporting the failure. For example `:load scratch.rs`.
+//!
+//! ## Types
+//! Results are printed with their type, for example `[out0]: i32 = 4`. This can be turned off with
+//! [`ReplData::show_result_type`](crate::repl::ReplData). The `type` command prints the type of an
+//! expression on demand, without keeping the expression. Previous results can be queried by name,
+//! for example `:type out0`, or `:type out0.len()`.
+//!
+//! ## Timing
+//! The `time` command evaluates an expression, without keeping it, and returns its type and result
+//! alongside the time taken to compile it and to execute it, for example
+//! `:time out0.iter().sum::<u64>()`. Execution time includes loading the compiled library. The
+//! `bench` command runs an expression in a loop, wrapped in `black_box`, and returns the mean,
+//! median, min, and max time of an iteration. It takes an optional number of iterations, which
+//! defaults to 1000, for example `:bench out0.len() 100`. The expression is evaluated in each
+//! iteration, so it can not move out of a local which is not `Copy`. Both commands return
+//! [structured values](#return-values).
+//!
+//! ## Documentation
+//! The `doc` command prints the signature, definition location, and documentation of an item, for
+//! example `:doc Vec::retain`. The path is resolved against the current REPL source code, so items
+//! defined in the REPL and in linked crates can be looked up. Requires the _racer-completion_
+//! feature, and the Rust source code for the standard library (`rustup component add rust-src`).
+//!
+//! ## Macro Expansion
+//! The `expand` command prints the macro expanded code of an input in the current module, which
+//! helps when debugging macro heavy code. `:expand` expands the last input and `:expand out0` expands
+//! the input of `out0`. The compiler expands the whole session with `-Zunpretty=expanded` and the
+//! statements of the input are cut out and formatted. This requires a nightly compiler, either as
+//! the default toolchain or installed through `rustup` as `nightly`.
+//!
 //! ## Modules
 //! The `mod` command allows more than just the `lib` module to exist in the REPL. Use `mod` to have
 //! different REPL sessions all sharing the same compilation cycle. This can be useful to switch
@@ -42,6 +86,21 @@
 //! glob patterns matching module paths, for example `:mod clear test/**` will clear all inputs under
 //! the module path `test/`. _`:mod clear` clears all previous REPL input in the **current module**._
 //!
+//! `:mod ls` lists the modules as a tree with the number of statements in each, the current module is
+//! marked with a `*`. Modules are removed with `rm`, which takes a module path or glob pattern and
+//! also removes the children of the matching modules, for example `:mod rm test` removes `test` and
+//! `test/inner`. The `lib` module cannot be removed, and if the current module is removed `lib` becomes
+//! current. `:mod mv from to` moves a module and its children to another path. Any imports added with
+//! `:mod use` follow a moved module, and are dropped when the module is removed.
+//!
+//! Modules can build on each other with the `use` command. `:mod use foo/bar` adds
+//! `use crate::foo::bar::*;` to the current module, importing the module's **public** items, so
+//! items that are to be shared need to be declared `pub`. It also adds an accessor to the latest
+//! result of the module, `foo_bar_out`, which re-evaluates the module's statements and returns the
+//! result and its type name as a `(Kserd, String)`. If the REPL is linked with app data, the
+//! accessor takes it as an argument, for example `foo_bar_out(app_data)`.
+//! Accessors calling each other in a cycle will overflow the stack.
+//!
 //! ## Static Files
 //! The `static-files` command allows the importing of file-system based rust documents into the REPL
 //! compilation. Rust files must be relative to the REPL working directory, and will be imported using
@@ -59,6 +118,44 @@
 //! used. To recursively add files `**/*.rs` can be used. This applies to removing static files using
 //! the `rm` command.
 //!
+//! Editing static files alongside the REPL is made easier with `:static-files watch on`. Before each
+//! evaluation the imported static files are read again, the files which changed are reloaded, and
+//! each reloaded file is reported. A file which was deleted is removed from the REPL.
+//! `:static-files watch off` stops watching.
+//!
+//! ## Linking Configuration
+//! The `env` command prints the linking configuration of the REPL: the app data type, whether a
+//! mutable block is active, the linked external libraries, crate name mappings, the async runtime,
+//! the compilation directory, and the persistent module code.
+//!
+//! The persistent module code, which is added to the top of every module, can be managed line by line
+//! with the `persist` command. `:persist add use std::collections::HashMap;` adds a line,
+//! `:persist ls` lists the lines with their index, and `:persist rm 0` removes the line at index 0. A
+//! change is checked by compiling the REPL code and is rejected if compilation fails.
+//!
+//! External libraries are linked with `extern`. `:extern add target/debug/libmycrate.rlib` links the
+//! library, which must be named `lib*.rlib` and have a `deps` directory next to it, as in a cargo
+//! target directory. A glob pattern links several libraries, and an alias can be given after the path
+//! of a single library, for example `:extern add target/debug/libmy_crate.rlib mc`. Rejected paths are
+//! reported with the reason. `:extern ls` lists the linked libraries and `:extern rm mc` removes a
+//! library by its name or alias.
+//!
+//! ## Sessions
+//! The `session` command saves and restores the REPL session. `:session save session.kserd` writes
+//! the modules and their inputs, the current module, static files, linked external libraries, and the
+//! persistent module code to a file. `:session load session.kserd` replaces the current session with
+//! the one in the file. The file is in the versioned [`kserd`](https://crates.io/crates/kserd) text
+//! format so it can be shared and resumed.
+//!
+//! ## Aliases
+//! The `alias` command defines a command which expands to a sequence of commands and code, the steps
+//! being separated by `;;`. For example `:alias alpha = :mod switch scratch/alpha ;; let x = 1; x`
+//! defines `:alpha`, which switches to the module and then evaluates the code. Aliases are listed in
+//! `:help` under their own heading, `:alias` lists them with their steps, and `:alias rm alpha`
+//! removes one. The `papyrus` binary saves aliases to the `[aliases]` table of
+//! `~/.config/papyrus/config.toml`, see
+//! [`ReplData::with_alias_config`](crate::repl::ReplData::with_alias_config).
+//!
 //! # Extending Commands
 //! ## Setup
 //!
@@ -68,7 +165,9 @@
 //! To begin, start a binary project with the following scaffolding in the main source code. We define
 //! a `custom_cmds` function that will be used to build our custom commands. To highlight the
 //! versatility of commands, the REPL is configured to have a persistent app data through a `String`.
-//! Notice also the method to alter the prompt name through the `Builder::new` method.
+//! Notice also the method to alter the prompt name through the `Builder::new` method. The
+//! function is given to `with_cmdtree_builder_fn` so the command tree can be rebuilt when aliases
+//! change, `with_cmdtree_builder` takes a single `Builder` but does not support aliases.
 //!
 //! ```rust,no_run
 //! #[macro_use]
@@ -86,7 +185,7 @@
 //!     let mut repl = repl!(String);
 //!
 //!     // Inject our custom commands.
-//!     repl.data.with_cmdtree_builder(custom_cmds()).unwrap();
+//!     repl.data.with_cmdtree_builder_fn(custom_cmds).unwrap();
 //!
 //!     // Create the persistent data.
 //!     let mut app_data = String::new();
@@ -198,21 +297,158 @@
 //! [lib] custom-cmds-app-mut=> app_data.push_str("Hello, world!")
 //! finished mutating block: ()
 //! [lib] custom-cmds-app=> app_data.as_str()
-//! custom-cmds-app [out0]: "Hello, world!"
+//! custom-cmds-app [out0]: &str = "Hello, world!"
 //! [lib] custom-cmds-app=> :case upper
 //! [lib] custom-cmds-app=> app_data.as_str()
-//! custom-cmds-app [out1]: "HELLO, WORLD!"
+//! custom-cmds-app [out1]: &str = "HELLO, WORLD!"
 //! [lib] custom-cmds-app=> :case lower
 //! [lib] custom-cmds-app=> app_data.as_str()
-//! custom-cmds-app [out2]: "hello, world!"
+//! custom-cmds-app [out2]: &str = "hello, world!"
+//! ```
+//!
+//! ## Return values
+//!
+//! An action can return a structured value rather than text, using `CommandResult::value_fn`. The
+//! value is printed through the same formatting configuration as evaluation results, and is handed to
+//! the callback set with `RunCallbacks::with_cmd_resultfn`. Let's add a `stats` action to the `case`
+//! class.
+//!
+//! ```rust
+//! # extern crate papyrus;
+//! # extern crate kserd;
+//! # use papyrus::cmdtree::BuilderChain;
+//! # use papyrus::cmds::CommandResult;
+//! # type Builder = papyrus::cmdtree::Builder<CommandResult<String>>;
+//! use kserd::Kserd;
+//!
+//! Builder::new("custom-cmds-app")
+//!     .begin_class("case", "change case of app_data")
+//!     .add_action("stats", "count the cases of app_data", |_, _|
+//!     CommandResult::<String>::value_fn(|app_data, _repldata, _| {
+//!         let upper = app_data.chars().filter(|c| c.is_uppercase()).count();
+//!         let lower = app_data.chars().filter(|c| c.is_lowercase()).count();
+//!         Kserd::new_cntr(vec![
+//!             ("upper", Kserd::new_num(upper)),
+//!             ("lower", Kserd::new_num(lower)),
+//!         ])
+//!         .unwrap()
+//!         })
+//!     )
+//!     .end_class()
+//!     .unwrap()
+//! # ;
+//! ```
+//!
+//! The value is prefixed with the command that returned it. With `Hello, World!` as the app data:
+//!
+//! ```text
+//! [lib] custom-cmds-app=> :case stats
+//! custom-cmds-app [:case stats]: (lower = 8, upper = 2)
+//! ```
+//!
+//! ## Long running tasks
+//!
+//! An action which takes a while, such as reloading data, can be run on a worker thread using
+//! `CommandResult::task_fn`. The action is handed a `Task` to write progress lines to the output as it
+//! goes, and to check if it has been cancelled. Pressing `Ctrl+C` cancels the task and returns to the
+//! prompt. The action does not borrow the app data. When the REPL is evaluating asynchronously, such as
+//! when run in the terminal, `Task::app_data` returns the shared `Arc<Mutex<D>>` to lock when required.
+//!
+//! ```rust
+//! # extern crate papyrus;
+//! # use papyrus::cmdtree::BuilderChain;
+//! # use papyrus::cmds::CommandResult;
+//! # type Builder = papyrus::cmdtree::Builder<CommandResult<String>>;
+//! Builder::new("custom-cmds-app")
+//!     .add_action("reload", "reload the data", |_, _|
+//!     CommandResult::<String>::task_fn(|task| {
+//!         for i in 1..=10 {
+//!             if task.is_cancelled() {
+//!                 break;
+//!             }
+//!             task.progress(&format!("loaded part {} of 10", i));
+//!             std::thread::sleep(std::time::Duration::from_millis(500));
+//!         }
+//!         if let Some(app_data) = task.app_data::<String>() {
+//!             app_data.lock().unwrap().push_str(" (reloaded)");
+//!         }
+//!         String::from("finished reloading")
+//!         })
+//!     )
+//!     .unwrap()
+//! # ;
+//! ```
+//!
+//! ```text
+//! [lib] custom-cmds-app=> :reload
+//! loaded part 1 of 10
+//! loaded part 2 of 10
+//! loaded part 3 of 10
+//! task cancelled
+//! [lib] custom-cmds-app=>
+//! ```
+//!
+//! ## Custom signals
+//!
+//! A command can ask the host application to do something outside of the REPL by sending a custom
+//! signal with `CommandResult::custom_signal`. The payload type is a type parameter of the REPL, which
+//! defaults to `()`, and can be set with the `repl!` macro. The payload is returned in the `EvalResult`
+//! as `Signal::Custom`, and is handed to the callback set with `RunCallbacks::with_signalfn`.
+//!
+//! ```rust,no_run
+//! #[macro_use]
+//! extern crate papyrus;
+//!
+//! use papyrus::cmdtree::{Builder, BuilderChain};
+//! use papyrus::cmds::CommandResult;
+//!
+//! enum AppSignal {
+//!     ReloadAssets,
+//! }
+//!
+//! # #[cfg(not(feature = "runnable"))]
+//! # fn main() {}
+//!
+//! # #[cfg(feature = "runnable")]
+//! fn main() {
+//!     let mut repl = repl!(String, AppSignal);
+//!
+//!     repl.data
+//!         .with_cmdtree_builder(
+//!             Builder::new("custom-cmds-app")
+//!                 .add_action("reload-assets", "reload the assets of the app", |_, _| {
+//!                     CommandResult::custom_signal(AppSignal::ReloadAssets)
+//!                 })
+//!                 .unwrap(),
+//!         )
+//!         .unwrap();
+//!
+//!     let mut app_data = String::new();
+//!
+//!     let callbacks = papyrus::run::RunCallbacks::new(&mut app_data).with_signalfn(
+//!         |signal, _repldata, _app_data| match signal {
+//!             AppSignal::ReloadAssets => println!("reloading assets"),
+//!         },
+//!     );
+//!
+//!     repl.run(callbacks).unwrap();
+//! }
 //! ```
 use super::*;
-use crate::repl::{Editing, EditingIndex, ReplData};
+use crate::linking::Extern;
+use crate::repl::{Editing, EditingIndex, MutBlock, ReplData};
 use cmdtree::{BuildError, Builder, BuilderChain, Commander};
+use kserd::Kserd;
 use std::{
+    any::Any,
+    collections::{BTreeMap, HashSet},
     fs,
     io::Write,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
 pub use cmdtree::Builder as CommandBuilder;
@@ -220,43 +456,167 @@ pub use cmdtree::Builder as CommandBuilder;
 /// The action to take. Passes through a mutable reference to the `ReplData`.
 ///
 /// Use [`CommandResult::repl_data_fn`](CommandResult::repl_data_fn) for convenience.
-pub type ReplDataAction<D> = Box<dyn Fn(&mut ReplData<D>, &mut dyn Write) -> String>;
+pub type ReplDataAction<D, S = ()> = Box<dyn Fn(&mut ReplData<D, S>, &mut dyn Write) -> String>;
 
 /// The action to take. Passes through a mutable reference to the data `D` _and_ the `ReplData<D>`.
 ///
 /// > _Mutably borrows_ `D` such that a lock must be taken. Use only when necessary.
 ///
 /// Use [`CommandResult::app_data_fn`](CommandResult::app_data_fn) for convenience.
-pub type AppDataAction<D> = Box<dyn Fn(&mut D, &mut ReplData<D>, &mut dyn Write) -> String>;
+pub type AppDataAction<D, S = ()> =
+    Box<dyn Fn(&mut D, &mut ReplData<D, S>, &mut dyn Write) -> String>;
+
+/// The action to take, returning a structured value. Passes through a mutable reference to the
+/// data `D` _and_ the `ReplData<D>`.
+///
+/// > _Mutably borrows_ `D` such that a lock must be taken. Use only when necessary.
+///
+/// Use [`CommandResult::value_fn`](CommandResult::value_fn) for convenience.
+pub type ValueAction<D, S = ()> =
+    Box<dyn Fn(&mut D, &mut ReplData<D, S>, &mut dyn Write) -> Kserd<'static>>;
+
+/// The action to take on a worker thread, returning the text to print once it finishes.
+///
+/// The action does not borrow the data `D` so no lock is held while it runs. Use the [`Task`] to
+/// write progress lines, to check if the action has been cancelled, and to lock the data when it
+/// is required.
+///
+/// Use [`CommandResult::task_fn`](CommandResult::task_fn) for convenience.
+pub type TaskAction = Box<dyn FnOnce(&Task) -> String + Send>;
+
+/// Handed to a [`TaskAction`] running on a worker thread.
+pub struct Task {
+    progress: crossbeam_channel::Sender<String>,
+    cancelled: Arc<AtomicBool>,
+    app_data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Task {
+    /// Write a progress line to the output.
+    pub fn progress(&self, line: &str) {
+        self.progress.send(line.to_string()).ok();
+    }
+
+    /// The task has been cancelled. Long running actions should check this regularly and return
+    /// early, the REPL does not wait on a cancelled task.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The shared app data, if the REPL is evaluating with
+    /// [`eval_async`](crate::repl::Repl::eval_async). Returns `None` when evaluating with
+    /// [`eval`](crate::repl::Repl::eval), which borrows the app data, or if `D` is not the type of
+    /// the app data.
+    ///
+    /// The app data is not locked while the task runs, lock it only for as long as it is needed.
+    pub fn app_data<D: 'static + Send>(&self) -> Option<Arc<Mutex<D>>> {
+        self.app_data.clone()?.downcast().ok()
+    }
+}
+
+/// Cancels the running [`TaskAction`], if any. Clones cancel the same tasks.
+///
+/// The REPL cancels a task with `Ctrl+C` when run in the terminal.
+/// Use [`ReplData::cancel_handle`](ReplData::cancel_handle) to get the handle.
+#[derive(Clone, Default)]
+pub struct CancelHandle(Arc<Mutex<Option<Arc<AtomicBool>>>>);
+
+impl CancelHandle {
+    /// Cancel the running task. Returns `false` if there is no task running.
+    pub fn cancel(&self) -> bool {
+        match &*self.0.lock().expect("failed getting lock of cancel handle") {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Begins a task, returning it and a receiver of its progress lines.
+    pub(crate) fn begin(
+        &self,
+        app_data: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> (Task, crossbeam_channel::Receiver<String>) {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        *self.0.lock().expect("failed getting lock of cancel handle") =
+            Some(Arc::clone(&cancelled));
+        let task = Task {
+            progress: tx,
+            cancelled,
+            app_data,
+        };
+        (task, rx)
+    }
+
+    /// The running task has been cancelled.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.0
+            .lock()
+            .expect("failed getting lock of cancel handle")
+            .as_ref()
+            .map(|cancelled| cancelled.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+
+    /// The running task has finished.
+    pub(crate) fn finish(&self) {
+        *self.0.lock().expect("failed getting lock of cancel handle") = None;
+    }
+}
 
 /// The result of a [`cmdtree action`].
 /// This result is handed in the repl's evaluating stage, and can alter `ReplData` or the data `D`.
 ///
 /// [`cmdtree action`]: cmdtree::Action
-pub enum CommandResult<D> {
+pub enum CommandResult<D, S = ()> {
     /// Flag to begin a mutating block.
     BeginMutBlock,
+    /// Flag to begin a mutating block with options.
+    BeginMutBlockWith(MutBlock),
+    /// Evaluate a persisted mutating block again.
+    ReplayMutBlock(usize),
     /// Flag to alter a previous statement, item, or crate.
     EditAlter(EditingIndex),
     /// Replace a previous statement, item, or crate with value.
     EditReplace(EditingIndex, String),
     /// Switch to a module.
     SwitchModule(PathBuf),
+    /// Load a source file, evaluating it as REPL input.
+    LoadFile(PathBuf),
+    /// Evaluate the expression, without keeping it, and print its type.
+    TypeOf(String),
+    /// Evaluate the expression, without keeping it, and print the compile and execution times.
+    Time(String),
+    /// Evaluate the expression a number of times, without keeping it, and print statistics of
+    /// the execution times.
+    Bench(String, usize),
+    /// Evaluate the steps of the named alias in turn.
+    RunAlias(String),
     /// Take an action on the `ReplData`.
-    ActionOnReplData(ReplDataAction<D>),
+    ActionOnReplData(ReplDataAction<D, S>),
     /// Take an action on data `D` and/or `ReplData`.
-    ActionOnAppData(AppDataAction<D>),
+    ActionOnAppData(AppDataAction<D, S>),
+    /// Take an action on data `D` and/or `ReplData`, returning a value which is printed and
+    /// handed back like the result of an evaluation.
+    ActionWithValue(ValueAction<D, S>),
+    /// Run an action on a worker thread, writing its progress until it finishes or is cancelled.
+    RunTask(TaskAction),
+    /// Send an application defined signal, returned from evaluating as
+    /// [`Signal::Custom`](crate::repl::Signal::Custom).
+    CustomSignal(S),
     /// A blank variant with no action.
     Empty,
 }
 
-impl<D> CommandResult<D> {
+impl<D, S> CommandResult<D, S> {
     /// Convenience function boxing an action on app data.
     ///
     /// > _Mutably borrows_ `D` such that a lock must be taken. Use only when necessary.
     pub fn app_data_fn<F>(func: F) -> Self
     where
-        F: 'static + Fn(&mut D, &mut ReplData<D>, &mut dyn Write) -> String,
+        F: 'static + Fn(&mut D, &mut ReplData<D, S>, &mut dyn Write) -> String,
     {
         CommandResult::ActionOnAppData(Box::new(func))
     }
@@ -264,33 +624,125 @@ impl<D> CommandResult<D> {
     /// Convenience function boxing an action on repl data.
     pub fn repl_data_fn<F>(func: F) -> Self
     where
-        F: 'static + Fn(&mut ReplData<D>, &mut dyn Write) -> String,
+        F: 'static + Fn(&mut ReplData<D, S>, &mut dyn Write) -> String,
     {
         CommandResult::ActionOnReplData(Box::new(func))
     }
+
+    /// Convenience function boxing an action returning a value.
+    ///
+    /// > _Mutably borrows_ `D` such that a lock must be taken. Use only when necessary.
+    pub fn value_fn<F>(func: F) -> Self
+    where
+        F: 'static + Fn(&mut D, &mut ReplData<D, S>, &mut dyn Write) -> Kserd<'static>,
+    {
+        CommandResult::ActionWithValue(Box::new(func))
+    }
+
+    /// Convenience function sending a custom signal with the payload.
+    pub fn custom_signal(payload: S) -> Self {
+        CommandResult::CustomSignal(payload)
+    }
+
+    /// Convenience function boxing an action to run on a worker thread.
+    pub fn task_fn<F>(func: F) -> Self
+    where
+        F: 'static + Send + FnOnce(&Task) -> String,
+    {
+        CommandResult::RunTask(Box::new(func))
+    }
 }
 
-impl<D> ReplData<D> {
+impl<D, S> ReplData<D, S> {
     /// Uses the given `Builder` as the root of the command tree.
     ///
     /// An error will be returned if any command already exists.
+    /// The command tree can not be rebuilt, so aliases can not be changed afterwards. Use
+    /// [`with_cmdtree_builder_fn`](ReplData::with_cmdtree_builder_fn) to support aliases.
     pub fn with_cmdtree_builder(
         &mut self,
-        builder: Builder<CommandResult<D>>,
+        builder: Builder<CommandResult<D, S>>,
     ) -> Result<&mut Self, BuildError> {
-        self.cmdtree = papyrus_cmdr(builder)?;
+        self.cmdtree = papyrus_cmdr(builder, &self.aliases)?;
+        self.cmdtree_builder = None;
         Ok(self)
     }
+
+    /// Uses the `Builder` returned from the function as the root of the command tree.
+    ///
+    /// The function is called again to rebuild the command tree when aliases change.
+    /// An error will be returned if any command already exists.
+    pub fn with_cmdtree_builder_fn<F>(&mut self, builder: F) -> Result<&mut Self, BuildError>
+    where
+        F: 'static + Send + Fn() -> Builder<CommandResult<D, S>>,
+    {
+        self.cmdtree = papyrus_cmdr(builder(), &self.aliases)?;
+        self.cmdtree_builder = Some(Box::new(builder));
+        Ok(self)
+    }
+
+    /// Rebuilds the command tree, picking up changes to the aliases.
+    ///
+    /// Does nothing if the command tree was set with a single `Builder`.
+    pub(crate) fn rebuild_cmdtree(&mut self) -> Result<(), BuildError> {
+        if let Some(builder) = &self.cmdtree_builder {
+            self.cmdtree = papyrus_cmdr(builder(), &self.aliases)?;
+        }
+        Ok(())
+    }
 }
 
-fn papyrus_cmdr<D>(
-    builder: Builder<CommandResult<D>>,
-) -> Result<Commander<CommandResult<D>>, BuildError> {
-    builder
+fn papyrus_cmdr<D, S>(
+    builder: Builder<CommandResult<D, S>>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Commander<CommandResult<D, S>>, BuildError> {
+    let mut builder = builder
         .root()
-        .add_action("mut", "Begin a mutable block of code", |_, _| {
-            CommandResult::BeginMutBlock
-        })
+        .add_action(
+            "alias",
+            "Define a command alias, steps are separated by `;;`. args: name = steps | rm name",
+            |wtr, args| alias_priv(args, wtr),
+        )
+        .add_action(
+            "mut",
+            "Begin a mutable block of code. args: [persist] [tx] | ls | replay index",
+            |wtr, args| mut_priv(args, wtr),
+        )
+        .add_action(
+            "load",
+            "Load a rust source file as REPL input. args: file-path",
+            |wtr, args| load_file_priv(args, wtr),
+        )
+        .add_action(
+            "type",
+            "Print the type of an expression. args: expr or outN",
+            |wtr, args| type_of_priv(args, wtr),
+        )
+        .add_action(
+            "doc",
+            "Print the documentation of an item. args: path",
+            |wtr, args| doc_priv(args, wtr),
+        )
+        .add_action(
+            "expand",
+            "Print the macro expanded code of an input. args: [outN]",
+            |wtr, args| expand_priv(args, wtr),
+        )
+        .add_action(
+            "time",
+            "Time the compilation and execution of an expression. args: expr",
+            |wtr, args| time_priv(args, wtr),
+        )
+        .add_action(
+            "bench",
+            "Benchmark an expression in a loop. args: expr [iterations]",
+            |wtr, args| bench_priv(args, wtr),
+        )
+        .add_action(
+            "env",
+            "Print the linking configuration and persistent module code",
+            |_, _| env_priv(),
+        )
         .begin_class("edit", "Edit previous input")
         .begin_class("stmt", "Edit previous statements")
         .add_action(
@@ -316,6 +768,26 @@ fn papyrus_cmdr<D>(
             "Clear previous input. args: mod-path or glob pattern",
             |wtr, args| clear_modules(args, wtr),
         )
+        .add_action(
+            "use",
+            "Import the items and latest result accessor of a module. args: mod-path",
+            |wtr, args| use_module_priv(args, wtr),
+        )
+        .add_action(
+            "ls",
+            "List the modules and their statement counts",
+            |_, _| ls_modules(),
+        )
+        .add_action(
+            "rm",
+            "Remove modules and their children. args: mod-path or glob pattern",
+            |wtr, args| rm_modules(args, wtr),
+        )
+        .add_action(
+            "mv",
+            "Move a module and its children. args: from-path to-path",
+            |wtr, args| mv_module_priv(args, wtr),
+        )
         .end_class()
         .begin_class("static-files", "Handle static files")
         .add_action(
@@ -329,90 +801,391 @@ fn papyrus_cmdr<D>(
             |wtr, args| rm_static_file(wtr, args),
         )
         .add_action("ls", "List imported static files", |_, _| ls_static_files())
+        .add_action(
+            "watch",
+            "Reload changed static files before each evaluation. args: on|off",
+            |wtr, args| watch_static_files(wtr, args),
+        )
         .end_class()
-        .into_commander()
-}
+        .begin_class("persist", "Handle persistent module code")
+        .add_action(
+            "add",
+            "Add a line of code to the top of each module. args: code",
+            |wtr, args| add_persistent_code(wtr, args),
+        )
+        .add_action("ls", "List the lines of persistent module code", |_, _| {
+            ls_persistent_code()
+        })
+        .add_action(
+            "rm",
+            "Remove a line of persistent module code. args: index",
+            |wtr, args| rm_persistent_code(wtr, args),
+        )
+        .end_class()
+        .begin_class("extern", "Handle linked external libraries")
+        .add_action(
+            "add",
+            "Link an external library. args: rlib-path or glob pattern [alias]",
+            |wtr, args| add_extern(wtr, args),
+        )
+        .add_action(
+            "rm",
+            "Remove a linked external library. args: name or alias",
+            |wtr, args| rm_extern(wtr, args),
+        )
+        .add_action("ls", "List linked external libraries", |_, _| ls_externs())
+        .end_class()
+        .begin_class("session", "Save and load REPL sessions")
+        .add_action(
+            "save",
+            "Save the current session to a file. args: file-path",
+            |wtr, args| save_session(wtr, args),
+        )
+        .add_action(
+            "load",
+            "Load a session from a file, replacing the current one. args: file-path",
+            |wtr, args| load_session(wtr, args),
+        )
+        .end_class();
 
-// ------ MODULES --------------------------------------------------------------
-fn switch_module_priv<D, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D> {
-    if let Some(path) = args.get(0) {
-        if let Some(path) = make_path(path) {
-            CommandResult::SwitchModule(path)
-        } else {
-            writeln!(wtr, "failed to parse {} into a valid module path", path).unwrap();
-            CommandResult::Empty
-        }
-    } else {
-        writeln!(wtr, "switch expects a path to module argument").unwrap();
-        CommandResult::Empty
+    for (name, steps) in aliases {
+        let alias = name.clone();
+        builder = builder.add_action(name, alias_help(steps), move |_, _| {
+            CommandResult::RunAlias(alias.clone())
+        });
     }
-}
 
-fn make_all_parents(path: &Path) -> Vec<PathBuf> {
-    let components: Vec<_> = path.iter().collect();
+    builder.into_commander()
+}
 
-    (1..components.len())
-        .map(|idx| components[0..idx].iter().collect::<PathBuf>())
-        .collect()
+// ------ ALIAS ----------------------------------------------------------------
+/// The help message of an alias action.
+pub(crate) fn alias_help(steps: &str) -> String {
+    format!("alias for `{}`", steps)
 }
 
-fn make_path(path: &str) -> Option<PathBuf> {
-    let path = path.trim();
+fn alias_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    let line = args.join(" ");
 
-    let path = path.replace(".rs", "").replace("mod", "").replace("-", "_");
+    if line.trim().is_empty() {
+        return CommandResult::repl_data_fn(|data, _| ls_aliases(data));
+    }
 
-    if path == "lib" {
-        return Some(PathBuf::from("lib"));
+    if let ["rm", name] = args {
+        let name = name.to_string();
+        return CommandResult::repl_data_fn(move |data, _| {
+            match data.remove_alias(&name).and_then(|_| data.save_aliases()) {
+                Ok(()) => String::new(),
+                Err(e) => e.to_string(),
+            }
+        });
     }
 
-    let x: &[_] = &['/', '\\'];
-    let path = path.trim_matches(x); // remove starting or trailing slashes
+    match parse_alias(&line) {
+        Some((name, steps)) => {
+            let (name, steps) = (name.to_string(), steps.to_string());
+            CommandResult::repl_data_fn(move |data, _| {
+                match data
+                    .with_alias(&name, &steps)
+                    .and_then(|data| data.save_aliases())
+                {
+                    Ok(()) => String::new(),
+                    Err(e) => e.to_string(),
+                }
+            })
+        }
+        None => {
+            writeln!(wtr, "alias expects `name = steps`").ok();
+            CommandResult::Empty
+        }
+    }
+}
 
-    if path.is_empty() {
-        return None;
+/// Splits `name = steps` into the name and steps.
+fn parse_alias(line: &str) -> Option<(&str, &str)> {
+    let idx = line.find('=')?;
+    let (name, steps) = (line[..idx].trim(), line[idx + 1..].trim());
+    if name.is_empty() || steps.is_empty() {
+        None
+    } else {
+        Some((name, steps))
     }
+}
 
-    Some(PathBuf::from(path))
+fn ls_aliases<D, S>(data: &mut ReplData<D, S>) -> String {
+    data.aliases()
+        .map(|(name, steps)| format!("{} = {}", name, steps))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn edit_alter_priv<D, W: Write>(args: &[&str], mut wtr: W, t: Editing) -> CommandResult<D> {
-    if let Some(idx) = args.get(0) {
-        match parse_idx(idx, t) {
-            Ok(ei) => CommandResult::EditAlter(ei),
-            Err(e) => {
-                writeln!(wtr, "failed parsing {} as number: {}", idx, e).ok();
+// ------ MUT ------------------------------------------------------------------
+fn mut_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    match args.first() {
+        None => CommandResult::BeginMutBlock,
+        Some(&"ls") => CommandResult::repl_data_fn(|data, _| ls_mut_blocks(data)),
+        Some(&"replay") => match args.get(1).map(|x| x.parse::<usize>()) {
+            Some(Ok(idx)) => CommandResult::ReplayMutBlock(idx),
+            Some(Err(e)) => {
+                writeln!(wtr, "failed parsing {} as number: {}", args[1], e).ok();
                 CommandResult::Empty
             }
+            None => {
+                writeln!(wtr, "replay expects an index number").ok();
+                CommandResult::Empty
+            }
+        },
+        Some(_) => {
+            let mut opts = MutBlock::default();
+            for arg in args {
+                match *arg {
+                    "persist" => opts.persist = true,
+                    "tx" => opts.transactional = true,
+                    x => {
+                        writeln!(wtr, "unknown mut option `{}`", x).ok();
+                        return CommandResult::Empty;
+                    }
+                }
+            }
+            CommandResult::BeginMutBlockWith(opts)
         }
+    }
+}
+
+fn ls_mut_blocks<D, S>(data: &mut ReplData<D, S>) -> String {
+    let mut s = String::new();
+    for (idx, (path, input, opts)) in data.persisted_mut_blocks.iter().enumerate() {
+        let src = input
+            .stmts
+            .iter()
+            .map(|x| x.expr.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        s.push_str(&format!(
+            "{} [{}]{} {}\n",
+            idx,
+            path.display(),
+            if opts.transactional { " tx" } else { "" },
+            src
+        ));
+    }
+    s.pop(); // remove the trailing new line
+    s
+}
+
+// ------ LOAD -----------------------------------------------------------------
+fn load_file_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    if let Some(path) = args.get(0) {
+        CommandResult::LoadFile(PathBuf::from(path))
     } else {
-        writeln!(wtr, "alter expects an index number").ok();
+        writeln!(wtr, "load expects a file path").ok();
         CommandResult::Empty
     }
 }
 
-fn edit_replace_priv<D, W: Write>(args: &[&str], mut wtr: W, t: Editing) -> CommandResult<D> {
-    if let Some(idx) = args.get(0) {
-        match parse_idx(idx, t) {
-            Ok(ei) => CommandResult::EditReplace(ei, args[1..].iter().copied().collect::<String>()),
-            Err(e) => {
-                writeln!(wtr, "failed parsing {} as number: {}", idx, e).ok();
-                CommandResult::Empty
-            }
-        }
-    } else {
-        writeln!(wtr, "replace expects an index number").ok();
+fn type_of_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    if args.is_empty() {
+        writeln!(wtr, "type expects an expression").ok();
         CommandResult::Empty
+    } else {
+        CommandResult::TypeOf(args.join(" "))
     }
 }
 
-fn parse_idx(s: &str, editing: Editing) -> Result<EditingIndex, String> {
-    s.parse()
-        .map_err(|e| format!("{}", e))
-        .map(|index| EditingIndex { editing, index })
+// ------ DOC ------------------------------------------------------------------
+fn doc_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    if args.is_empty() {
+        writeln!(wtr, "doc expects a path").ok();
+        CommandResult::Empty
+    } else {
+        let path = args.join(" ");
+        CommandResult::repl_data_fn(move |data, _| lookup_doc(data, &path))
+    }
 }
 
-pub(crate) fn edit_alter<D>(data: &mut ReplData<D>, ei: EditingIndex) -> &'static str {
-    let src = data.current_src();
+#[cfg(feature = "racer-completion")]
+fn lookup_doc<D, S>(data: &mut ReplData<D, S>, path: &str) -> String {
+    use crate::complete::code::{CodeCache, CodeCompleter};
+
+    let cache = CodeCache::new().unwrap_or_else(|e| e.0);
+    let cmpltr = CodeCompleter::build(data);
+
+    match cmpltr.definition(path, &cache) {
+        Some(m) => format_doc(
+            &m.contextstr,
+            &m.filepath,
+            m.coords.map(|x| x.row.0),
+            &m.docs,
+        ),
+        None => format!("no definition found for `{}`", path),
+    }
+}
+
+#[cfg(not(feature = "racer-completion"))]
+fn lookup_doc<D, S>(_: &mut ReplData<D, S>, _: &str) -> String {
+    String::from("doc requires the racer-completion feature")
+}
+
+/// Formats the signature, definition location, and documentation of an item.
+#[cfg_attr(not(feature = "racer-completion"), allow(dead_code))]
+fn format_doc(signature: &str, file: &Path, line: Option<u32>, docs: &str) -> String {
+    let mut s = format!("{}\n  --> {}", signature.trim(), file.display());
+    if let Some(line) = line {
+        s.push_str(&format!(":{}", line));
+    }
+    s.push('\n');
+    if docs.trim().is_empty() {
+        s.push_str("no documentation");
+    } else {
+        s.push_str(docs.trim_end());
+    }
+    s
+}
+
+// ------ EXPAND ---------------------------------------------------------------
+fn expand_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    let index = match args.first() {
+        None => None,
+        Some(arg) => match arg.trim_start_matches("out").parse::<usize>() {
+            Ok(i) => Some(i),
+            Err(e) => {
+                writeln!(wtr, "failed parsing {} as number: {}", arg, e).ok();
+                return CommandResult::Empty;
+            }
+        },
+    };
+
+    CommandResult::repl_data_fn(move |data, _| match data.expand_input(index) {
+        Ok(s) => s,
+        Err(e) => e.to_string(),
+    })
+}
+
+// ------ TIME -----------------------------------------------------------------
+/// The number of iterations `bench` runs if not specified.
+const BENCH_ITERATIONS: usize = 1000;
+
+fn time_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    if args.is_empty() {
+        writeln!(wtr, "time expects an expression").ok();
+        CommandResult::Empty
+    } else {
+        CommandResult::Time(args.join(" "))
+    }
+}
+
+fn bench_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    match bench_args(args) {
+        None => {
+            writeln!(wtr, "bench expects an expression").ok();
+            CommandResult::Empty
+        }
+        Some((_, 0)) => {
+            writeln!(wtr, "bench expects at least one iteration").ok();
+            CommandResult::Empty
+        }
+        Some((expr, iterations)) => CommandResult::Bench(expr, iterations),
+    }
+}
+
+/// Splits the expression and the number of iterations.
+///
+/// A trailing number is the number of iterations, unless it completes the expression, such as
+/// `x + 1`.
+fn bench_args(args: &[&str]) -> Option<(String, usize)> {
+    match args.split_last() {
+        None => None,
+        Some((last, rest)) if !rest.is_empty() => {
+            let expr = rest.join(" ");
+            match last.parse::<usize>() {
+                Ok(n) if !crate::input::needs_more(&expr) => Some((expr, n)),
+                _ => Some((args.join(" "), BENCH_ITERATIONS)),
+            }
+        }
+        Some(_) => Some((args.join(" "), BENCH_ITERATIONS)),
+    }
+}
+
+// ------ MODULES --------------------------------------------------------------
+fn switch_module_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    if let Some(path) = args.get(0) {
+        if let Some(path) = make_path(path) {
+            CommandResult::SwitchModule(path)
+        } else {
+            writeln!(wtr, "failed to parse {} into a valid module path", path).unwrap();
+            CommandResult::Empty
+        }
+    } else {
+        writeln!(wtr, "switch expects a path to module argument").unwrap();
+        CommandResult::Empty
+    }
+}
+
+fn make_all_parents(path: &Path) -> Vec<PathBuf> {
+    let components: Vec<_> = path.iter().collect();
+
+    (1..components.len())
+        .map(|idx| components[0..idx].iter().collect::<PathBuf>())
+        .collect()
+}
+
+fn make_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+
+    let path = path.replace(".rs", "").replace("mod", "").replace("-", "_");
+
+    if path == "lib" {
+        return Some(PathBuf::from("lib"));
+    }
+
+    let x: &[_] = &['/', '\\'];
+    let path = path.trim_matches(x); // remove starting or trailing slashes
+
+    if path.is_empty() {
+        return None;
+    }
+
+    Some(PathBuf::from(path))
+}
+
+fn edit_alter_priv<D, S, W: Write>(args: &[&str], mut wtr: W, t: Editing) -> CommandResult<D, S> {
+    if let Some(idx) = args.get(0) {
+        match parse_idx(idx, t) {
+            Ok(ei) => CommandResult::EditAlter(ei),
+            Err(e) => {
+                writeln!(wtr, "failed parsing {} as number: {}", idx, e).ok();
+                CommandResult::Empty
+            }
+        }
+    } else {
+        writeln!(wtr, "alter expects an index number").ok();
+        CommandResult::Empty
+    }
+}
+
+fn edit_replace_priv<D, S, W: Write>(args: &[&str], mut wtr: W, t: Editing) -> CommandResult<D, S> {
+    if let Some(idx) = args.get(0) {
+        match parse_idx(idx, t) {
+            Ok(ei) => CommandResult::EditReplace(ei, args[1..].iter().copied().collect::<String>()),
+            Err(e) => {
+                writeln!(wtr, "failed parsing {} as number: {}", idx, e).ok();
+                CommandResult::Empty
+            }
+        }
+    } else {
+        writeln!(wtr, "replace expects an index number").ok();
+        CommandResult::Empty
+    }
+}
+
+fn parse_idx(s: &str, editing: Editing) -> Result<EditingIndex, String> {
+    s.parse()
+        .map_err(|e| format!("{}", e))
+        .map(|index| EditingIndex { editing, index })
+}
+
+pub(crate) fn edit_alter<D, S>(data: &mut ReplData<D, S>, ei: EditingIndex) -> &'static str {
+    let src = data.current_src();
 
     let len = match ei.editing {
         Editing::Stmt => src.stmts.len(),
@@ -428,7 +1201,7 @@ pub(crate) fn edit_alter<D>(data: &mut ReplData<D>, ei: EditingIndex) -> &'stati
     }
 }
 
-pub(crate) fn switch_module<D>(data: &mut ReplData<D>, path: &Path) -> &'static str {
+pub(crate) fn switch_module<D, S>(data: &mut ReplData<D, S>, path: &Path) -> &'static str {
     let mut all = make_all_parents(path);
     all.push(path.to_path_buf());
 
@@ -441,7 +1214,7 @@ pub(crate) fn switch_module<D>(data: &mut ReplData<D>, path: &Path) -> &'static
     ""
 }
 
-fn clear_modules<D, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D> {
+fn clear_modules<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
     if let Some(pat) = args.get(0) {
         match glob::Pattern::new(pat) {
             Ok(pattern) => CommandResult::repl_data_fn(move |data, wtr| {
@@ -470,8 +1243,276 @@ fn clear_modules<D, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D> {
     }
 }
 
+fn use_module_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    let path = match args.first().map(|p| (p, make_path(p))) {
+        Some((_, Some(path))) => path,
+        Some((p, None)) => {
+            writeln!(wtr, "failed to parse {} into a valid module path", p).ok();
+            return CommandResult::Empty;
+        }
+        None => {
+            writeln!(wtr, "use expects a path to module argument").ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    CommandResult::repl_data_fn(move |data, wtr| {
+        if !data.mods_map.contains_key(&path) {
+            return format!("module `{}` does not exist", path.display());
+        }
+        if path == data.current_mod() {
+            return String::from("cannot use the current module");
+        }
+
+        let (items, accessor) = use_module_items(&path);
+        let src = data
+            .mods_map
+            .get_mut(&data.current_mod)
+            .expect("current module should exist");
+        for item in &[items, accessor] {
+            if !src.items.iter().any(|x| &x.0 == item) {
+                writeln!(wtr, "{}", item).ok();
+                src.items.push((item.clone(), false));
+            }
+        }
+
+        format!("using module `{}`", path.display())
+    })
+}
+
+/// Returns the `use` item importing the module's items, and the `use` item aliasing the module's
+/// evaluation function as `<mod_path>_out`.
+fn use_module_items(path: &Path) -> (String, String) {
+    let lib = path == Path::new("lib");
+    let mods = crate::code::into_mod_path_vec(path);
+
+    let mut mod_path = String::from("crate");
+    if !lib {
+        for m in &mods {
+            mod_path.push_str("::");
+            mod_path.push_str(m);
+        }
+    }
+
+    let mut fn_name = String::new();
+    crate::code::eval_fn_name(&mods, &mut fn_name);
+
+    (
+        format!("use {}::*;", mod_path),
+        format!("use {}::{} as {}_out;", mod_path, fn_name, mods.join("_")),
+    )
+}
+
+fn ls_modules<D, S>() -> CommandResult<D, S> {
+    CommandResult::repl_data_fn(|data, wtr| {
+        for (path, src) in &data.mods_map {
+            let current = if path == &data.current_mod { '*' } else { ' ' };
+            let indent = "  ".repeat(path.iter().count().saturating_sub(1));
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let n = src.stmts.len();
+            let s = if n == 1 { "" } else { "s" };
+            writeln!(wtr, "{} {}{} ({} stmt{})", current, indent, name, n, s).ok();
+        }
+        String::new()
+    })
+}
+
+fn rm_modules<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    let pat = match args.first() {
+        Some(pat) => *pat,
+        None => {
+            writeln!(wtr, "rm expects a mod-path or glob pattern").ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    match glob::Pattern::new(pat) {
+        Ok(pattern) => {
+            let pat = pat.to_string();
+            CommandResult::repl_data_fn(move |data, wtr| {
+                if pattern.matches_path(Path::new("lib")) {
+                    writeln!(wtr, "cannot remove `lib`").ok();
+                }
+
+                let removed = remove_modules(data, &pattern);
+                for path in &removed {
+                    writeln!(wtr, "removed module `{}`", path.display()).ok();
+                }
+
+                if removed.is_empty() {
+                    format!("no modules removed matching `{}`", pat)
+                } else {
+                    format!("current module is `{}`", data.current_mod.display())
+                }
+            })
+        }
+        Err(e) => {
+            writeln!(wtr, "unrecognisable pattern: {}", e).ok();
+            CommandResult::Empty
+        }
+    }
+}
+
+/// Removes the modules matching the pattern, and their children. `lib` is never removed.
+/// If the current module is removed, `lib` becomes the current module.
+fn remove_modules<D, S>(data: &mut ReplData<D, S>, pattern: &glob::Pattern) -> Vec<PathBuf> {
+    let lib = Path::new("lib");
+
+    let matched = data
+        .mods_map
+        .keys()
+        .filter(|path| *path != lib && pattern.matches_path(path))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let removed = data
+        .mods_map
+        .keys()
+        .filter(|path| matched.iter().any(|x| path.starts_with(x)))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for path in &removed {
+        data.mods_map.remove(path);
+    }
+
+    for path in &removed {
+        rewrite_module_uses(data, path, None);
+    }
+
+    data.persisted_mut_blocks
+        .retain(|(path, _, _)| !removed.contains(path));
+
+    if removed.contains(&data.current_mod) {
+        data.current_mod = lib.to_path_buf();
+        data.editing = None;
+    }
+
+    removed
+}
+
+fn mv_module_priv<D, S, W: Write>(args: &[&str], mut wtr: W) -> CommandResult<D, S> {
+    let paths = match args {
+        [from, to] => make_path(from).zip(make_path(to)),
+        _ => {
+            writeln!(wtr, "mv expects a from and to module path").ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    match paths {
+        Some((from, to)) => {
+            CommandResult::repl_data_fn(move |data, _| match move_module(data, &from, &to) {
+                Ok(()) => format!("moved module `{}` to `{}`", from.display(), to.display()),
+                Err(e) => e,
+            })
+        }
+        None => {
+            writeln!(
+                wtr,
+                "failed to parse {} into valid module paths",
+                args.join(" ")
+            )
+            .ok();
+            CommandResult::Empty
+        }
+    }
+}
+
+/// Moves the module and its children to another path, creating the parents of the new path.
+fn move_module<D, S>(data: &mut ReplData<D, S>, from: &Path, to: &Path) -> Result<(), String> {
+    if from == Path::new("lib") {
+        return Err(String::from("cannot move `lib`"));
+    }
+    if !data.mods_map.contains_key(from) {
+        return Err(format!("module `{}` does not exist", from.display()));
+    }
+    if data.mods_map.contains_key(to) {
+        return Err(format!("module `{}` already exists", to.display()));
+    }
+    if to.starts_with(from) {
+        return Err(format!("cannot move `{}` into itself", from.display()));
+    }
+
+    let rename = |path: &Path| {
+        path.strip_prefix(from).ok().map(|rel| {
+            if rel.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(rel)
+            }
+        })
+    };
+
+    let moving = data
+        .mods_map
+        .keys()
+        .filter(|path| path.starts_with(from))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for path in &moving {
+        if let Some(src) = data.mods_map.remove(path) {
+            data.mods_map
+                .insert(rename(path).expect("starts with from"), src);
+        }
+    }
+
+    for parent in make_all_parents(to) {
+        data.mods_map.entry(parent).or_default();
+    }
+
+    for path in &moving {
+        rewrite_module_uses(data, path, rename(path).as_deref());
+    }
+
+    for (path, _, _) in &mut data.persisted_mut_blocks {
+        if let Some(new) = rename(path) {
+            *path = new;
+        }
+    }
+
+    if let Some(new) = rename(&data.current_mod) {
+        data.current_mod = new;
+    }
+
+    Ok(())
+}
+
+/// Rewrites the items added by `mod use` which import the module at `from` to import `to`
+/// instead, keeping the name of the evaluation function alias. The items are removed if `to` is
+/// `None`.
+fn rewrite_module_uses<D, S>(data: &mut ReplData<D, S>, from: &Path, to: Option<&Path>) {
+    let accessor_prefix = |path: &Path| {
+        let (_, accessor) = use_module_items(path);
+        let idx = accessor.rfind(" as ").expect("accessor is aliased") + 4;
+        accessor[..idx].to_string()
+    };
+
+    let (items, _) = use_module_items(from);
+    let prefix = accessor_prefix(from);
+    let to = to.map(|to| (use_module_items(to).0, accessor_prefix(to)));
+
+    for src in data.mods_map.values_mut() {
+        match &to {
+            Some((to_items, to_prefix)) => {
+                for (item, _) in &mut src.items {
+                    if *item == items {
+                        *item = to_items.clone();
+                    } else if let Some(alias) = item.strip_prefix(&prefix) {
+                        *item = format!("{}{}", to_prefix, alias);
+                    }
+                }
+            }
+            None => src
+                .items
+                .retain(|(item, _)| *item != items && !item.starts_with(&prefix)),
+        }
+    }
+}
+
 // ------ STATIC FILES ---------------------------------------------------------
-fn add_static_file<D>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D> {
+fn add_static_file<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
     if let Some(&path) = args.get(0) {
         let glob = path.to_string();
         CommandResult::repl_data_fn(move |data, wtr| {
@@ -495,7 +1536,7 @@ fn add_static_file<D>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D> {
     }
 }
 
-fn rm_static_file<D>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D> {
+fn rm_static_file<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
     if let Some(&path) = args.get(0) {
         let glob = path.to_string();
         CommandResult::repl_data_fn(move |data, wtr| {
@@ -512,7 +1553,7 @@ fn rm_static_file<D>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D> {
     }
 }
 
-fn ls_static_files<D>() -> CommandResult<D> {
+fn ls_static_files<D, S>() -> CommandResult<D, S> {
     CommandResult::repl_data_fn(|data, wtr| {
         let sfs = data.static_files();
         if sfs.is_empty() {
@@ -530,6 +1571,269 @@ fn ls_static_files<D>() -> CommandResult<D> {
     })
 }
 
+fn watch_static_files<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    let watch = match args.first() {
+        Some(&"on") => true,
+        Some(&"off") => false,
+        _ => {
+            writeln!(wtr, "watch expects on or off").ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    CommandResult::repl_data_fn(move |data, _| {
+        data.watch_static_files = watch;
+        if watch {
+            String::from("watching static files for changes")
+        } else {
+            String::from("stopped watching static files")
+        }
+    })
+}
+
+// ------ LINKING --------------------------------------------------------------
+fn env_priv<D, S>() -> CommandResult<D, S> {
+    CommandResult::repl_data_fn(|data, wtr| {
+        let linking = data.linking();
+
+        match &linking.data_type {
+            Some(ty) => writeln!(wtr, "data type: {}", ty),
+            None => writeln!(wtr, "data type: none"),
+        }
+        .ok();
+        writeln!(wtr, "mutable: {}", linking.mutable).ok();
+
+        writeln!(wtr, "external libraries:").ok();
+        write_externs(wtr, "\t", &linking.external_libs);
+
+        writeln!(wtr, "crate names:").ok();
+        let mut names = linking.crate_names.iter().collect::<Vec<_>>();
+        names.sort();
+        for (name, cargo_name) in names {
+            writeln!(wtr, "\t{} -> {}", name, cargo_name).ok();
+        }
+
+        match &linking.async_runtime {
+            Some(rt) => writeln!(wtr, "async runtime: {}", rt),
+            None => writeln!(wtr, "async runtime: bundled"),
+        }
+        .ok();
+        writeln!(
+            wtr,
+            "compilation directory: {}",
+            data.compilation_dir().display()
+        )
+        .ok();
+
+        writeln!(wtr, "persistent module code:").ok();
+        for line in linking.persistent_module_code.lines() {
+            writeln!(wtr, "\t{}", line).ok();
+        }
+
+        String::new()
+    })
+}
+
+fn add_persistent_code<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    if args.is_empty() {
+        writeln!(wtr, "add expects a line of code").ok();
+        return CommandResult::Empty;
+    }
+
+    let code = args.join(" ");
+    CommandResult::repl_data_fn(move |data, _| {
+        let prev = data.persistent_module_code().clone();
+        let persistent = data.persistent_module_code();
+        if !persistent.is_empty() && !persistent.ends_with('\n') {
+            persistent.push('\n');
+        }
+        persistent.push_str(&code);
+
+        match data.trial_compile() {
+            Ok(()) => format!("added persistent module code `{}`", code),
+            Err(e) => {
+                *data.persistent_module_code() = prev;
+                format!(
+                    "persistent module code rejected, compilation failed:\n{}",
+                    e
+                )
+            }
+        }
+    })
+}
+
+fn ls_persistent_code<D, S>() -> CommandResult<D, S> {
+    CommandResult::repl_data_fn(|data, wtr| {
+        let code = data.persistent_module_code();
+        if code.trim().is_empty() {
+            writeln!(wtr, "no persistent module code").ok();
+        }
+        for (idx, line) in code.lines().enumerate() {
+            writeln!(wtr, "{}: {}", idx, line).ok();
+        }
+        String::new()
+    })
+}
+
+fn rm_persistent_code<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    let idx = match args.first().map(|x| x.parse::<usize>()) {
+        Some(Ok(idx)) => idx,
+        Some(Err(e)) => {
+            writeln!(wtr, "failed parsing {} as number: {}", args[0], e).ok();
+            return CommandResult::Empty;
+        }
+        None => {
+            writeln!(wtr, "rm expects an index number").ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    CommandResult::repl_data_fn(move |data, _| {
+        let prev = data.persistent_module_code().clone();
+        let mut lines = prev.lines().collect::<Vec<_>>();
+        if idx >= lines.len() {
+            return String::from("index is outside of range");
+        }
+        let removed = lines.remove(idx).to_string();
+        *data.persistent_module_code() = lines.join("\n");
+
+        match data.trial_compile() {
+            Ok(()) => format!("removed persistent module code `{}`", removed),
+            Err(e) => {
+                *data.persistent_module_code() = prev;
+                format!("removal rejected, compilation failed:\n{}", e)
+            }
+        }
+    })
+}
+
+fn add_extern<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    let (path, alias) = match args {
+        [path] => (path.to_string(), None),
+        [path, alias] => (path.to_string(), Some(alias.to_string())),
+        _ => {
+            writeln!(
+                wtr,
+                "add expects an rlib path or glob pattern, and an optional alias"
+            )
+            .ok();
+            return CommandResult::Empty;
+        }
+    };
+
+    if let Some(alias) = &alias {
+        if alias.is_empty() || !alias.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            writeln!(wtr, "`{}` is not a valid alias", alias).ok();
+            return CommandResult::Empty;
+        }
+    }
+
+    CommandResult::repl_data_fn(move |data, wtr| {
+        let paths = if path.contains(&['*', '?', '['][..]) {
+            let mut paths = Vec::new();
+            foreach_glob_path(&path, wtr, |p, _| paths.push(p));
+            paths
+        } else {
+            vec![PathBuf::from(&path)]
+        };
+
+        if paths.is_empty() {
+            return format!("no files matched `{}`", path);
+        }
+        if alias.is_some() && paths.len() > 1 {
+            return format!(
+                "an alias can only be given to one library, `{}` matched {}",
+                path,
+                paths.len()
+            );
+        }
+
+        for path in paths {
+            let ext = match &alias {
+                Some(alias) => Extern::with_alias(&path, alias),
+                None => Extern::new(&path),
+            };
+            match ext {
+                Ok(ext) => {
+                    writeln!(wtr, "linked external library `{}`", ext.lib_name()).ok();
+                    data.with_external_lib(ext);
+                }
+                Err(e) => {
+                    writeln!(wtr, "rejected `{}`: {}", path.display(), e).ok();
+                }
+            }
+        }
+
+        String::new()
+    })
+}
+
+fn rm_extern<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    if let Some(&name) = args.first() {
+        let name = name.to_string();
+        CommandResult::repl_data_fn(move |data, _| {
+            if data.remove_external_lib(&name) {
+                format!("removed external library `{}`", name)
+            } else {
+                format!("no external library named `{}`", name)
+            }
+        })
+    } else {
+        writeln!(wtr, "rm expects a library name or alias").ok();
+        CommandResult::Empty
+    }
+}
+
+fn ls_externs<D, S>() -> CommandResult<D, S> {
+    CommandResult::repl_data_fn(|data, wtr| {
+        let externs = &data.linking().external_libs;
+        if externs.is_empty() {
+            writeln!(wtr, "no external libraries linked").ok();
+        }
+        write_externs(wtr, "", externs);
+        String::new()
+    })
+}
+
+fn write_externs(wtr: &mut dyn Write, indent: &str, externs: &HashSet<Extern>) {
+    let mut externs = externs.iter().collect::<Vec<_>>();
+    externs.sort_by_key(|x| x.lib_name());
+    for ext in externs {
+        write!(wtr, "{}{}", indent, ext.lib_name()).ok();
+        if let Some(alias) = ext.alias() {
+            write!(wtr, " as {}", alias).ok();
+        }
+        writeln!(wtr, " -- {}", ext.lib_path().display()).ok();
+    }
+}
+
+// ------ SESSIONS -------------------------------------------------------------
+fn save_session<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    if let Some(&path) = args.get(0) {
+        let path = PathBuf::from(path);
+        CommandResult::repl_data_fn(move |data, _| match data.save_session(&path) {
+            Ok(_) => format!("saved session to `{}`", path.display()),
+            Err(e) => format!("failed to save session: {}", e),
+        })
+    } else {
+        writeln!(wtr, "save expects a file path").ok();
+        CommandResult::Empty
+    }
+}
+
+fn load_session<D, S>(wtr: &mut dyn Write, args: &[&str]) -> CommandResult<D, S> {
+    if let Some(&path) = args.get(0) {
+        let path = PathBuf::from(path);
+        CommandResult::repl_data_fn(move |data, _| match data.load_session(&path) {
+            Ok(_) => format!("loaded session from `{}`", path.display()),
+            Err(e) => format!("failed to load session: {}", e),
+        })
+    } else {
+        writeln!(wtr, "load expects a file path").ok();
+        CommandResult::Empty
+    }
+}
+
 fn foreach_glob_path<F>(glob: &str, wtr: &mut dyn Write, mut f: F)
 where
     F: FnMut(PathBuf, &mut dyn Write),
@@ -550,6 +1854,44 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn format_doc_test() {
+        assert_eq!(
+            format_doc(
+                "pub fn retain<F>(&mut self, f: F)",
+                Path::new("vec/mod.rs"),
+                Some(1234),
+                "Retains only the elements specified by the predicate.\n"
+            ),
+            "pub fn retain<F>(&mut self, f: F)\n  --> vec/mod.rs:1234\nRetains only the elements specified by the predicate."
+        );
+        assert_eq!(
+            format_doc("struct Foo", Path::new("lib.rs"), None, ""),
+            "struct Foo\n  --> lib.rs\nno documentation"
+        );
+    }
+
+    #[test]
+    fn bench_args_test() {
+        assert_eq!(bench_args(&[]), None);
+        assert_eq!(
+            bench_args(&["1"]),
+            Some(("1".to_string(), BENCH_ITERATIONS))
+        );
+        assert_eq!(
+            bench_args(&["out0.len()", "50"]),
+            Some(("out0.len()".to_string(), 50))
+        );
+        assert_eq!(
+            bench_args(&["x", "+", "1"]),
+            Some(("x + 1".to_string(), BENCH_ITERATIONS))
+        );
+        assert_eq!(
+            bench_args(&["x", "+", "1", "0"]),
+            Some(("x + 1".to_string(), 0))
+        );
+    }
+
     #[test]
     fn make_path_test() {
         assert_eq!(make_path("   "), None);
@@ -566,6 +1908,202 @@ mod tests {
         assert_eq!(make_path("\\hello\\"), Some(PathBuf::from("hello")));
     }
 
+    #[test]
+    fn remove_modules_test() {
+        let mut data = ReplData::<()>::default();
+        switch_module(&mut data, Path::new("a/b/c"));
+        switch_module(&mut data, Path::new("ab"));
+        switch_module(&mut data, Path::new("a/b"));
+
+        let pattern = glob::Pattern::new("a/b").unwrap();
+        assert_eq!(
+            remove_modules(&mut data, &pattern),
+            vec![PathBuf::from("a/b"), PathBuf::from("a/b/c")]
+        );
+        assert_eq!(data.current_mod(), Path::new("lib"));
+
+        let pattern = glob::Pattern::new("*").unwrap();
+        assert_eq!(
+            remove_modules(&mut data, &pattern),
+            vec![PathBuf::from("a"), PathBuf::from("ab")]
+        );
+        assert_eq!(
+            data.mods_map().keys().collect::<Vec<_>>(),
+            vec![Path::new("lib")]
+        );
+    }
+
+    #[test]
+    fn move_module_test() {
+        let mut data = ReplData::<()>::default();
+        switch_module(&mut data, Path::new("a/b/c"));
+        switch_module(&mut data, Path::new("a/b"));
+
+        assert!(move_module(&mut data, Path::new("lib"), Path::new("x")).is_err());
+        assert!(move_module(&mut data, Path::new("z"), Path::new("x")).is_err());
+        assert!(move_module(&mut data, Path::new("a"), Path::new("lib")).is_err());
+        assert!(move_module(&mut data, Path::new("a"), Path::new("a/b/d")).is_err());
+
+        move_module(&mut data, Path::new("a/b"), Path::new("x/y")).unwrap();
+        assert_eq!(
+            data.mods_map().keys().collect::<Vec<_>>(),
+            vec![
+                Path::new("a"),
+                Path::new("lib"),
+                Path::new("x"),
+                Path::new("x/y"),
+                Path::new("x/y/c")
+            ]
+        );
+        assert_eq!(data.current_mod(), Path::new("x/y"));
+    }
+
+    #[test]
+    fn module_uses_follow_rm_and_mv_test() {
+        let mut data = ReplData::<()>::default();
+        switch_module(&mut data, Path::new("a/b"));
+        switch_module(&mut data, Path::new("c"));
+        switch_module(&mut data, Path::new("lib"));
+
+        let use_module = |data: &mut ReplData<()>, path: &str| {
+            let (items, accessor) = use_module_items(Path::new(path));
+            let src = data.mods_map.get_mut(&data.current_mod).unwrap();
+            src.items.push((items, false));
+            src.items.push((accessor, false));
+        };
+        let items = |data: &ReplData<()>| {
+            data.mods_map()
+                .get(Path::new("lib"))
+                .unwrap()
+                .items
+                .iter()
+                .map(|x| x.0.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        use_module(&mut data, "a/b");
+        use_module(&mut data, "c");
+
+        move_module(&mut data, Path::new("a"), Path::new("x")).unwrap();
+        assert_eq!(
+            items(&data),
+            "use crate::x::b::*; use crate::x::b::_x_b_intern_eval as a_b_out; \
+             use crate::c::*; use crate::c::_c_intern_eval as c_out;"
+        );
+
+        let pattern = glob::Pattern::new("x/b").unwrap();
+        remove_modules(&mut data, &pattern);
+        assert_eq!(
+            items(&data),
+            "use crate::c::*; use crate::c::_c_intern_eval as c_out;"
+        );
+    }
+
+    #[test]
+    fn extern_cmds_test() {
+        let dir = Path::new("target/testing/extern_cmds");
+        fs::create_dir_all(dir.join("good/deps")).unwrap();
+        fs::create_dir_all(dir.join("nodeps")).unwrap();
+        fs::write(dir.join("good/libgood.rlib"), "").unwrap();
+        fs::write(dir.join("good/libother.rlib"), "").unwrap();
+        fs::write(dir.join("good/bad.rlib"), "").unwrap();
+        fs::write(dir.join("nodeps/libnodeps.rlib"), "").unwrap();
+
+        fn run(data: &mut ReplData<()>, cmd: CommandResult<()>) -> String {
+            match cmd {
+                CommandResult::ActionOnReplData(f) => {
+                    let mut buf = Vec::new();
+                    let s = f(data, &mut buf);
+                    String::from_utf8(buf).unwrap() + &s
+                }
+                _ => panic!("expecting action on repl data"),
+            }
+        }
+
+        let mut data = ReplData::<()>::default();
+
+        let out = run(
+            &mut data,
+            add_extern(
+                &mut Vec::new(),
+                &["target/testing/extern_cmds/good/bad.rlib"],
+            ),
+        );
+        assert!(out.starts_with("rejected `target/testing/extern_cmds/good/bad.rlib`: library must be in format lib*.rlib"));
+
+        let out = run(
+            &mut data,
+            add_extern(
+                &mut Vec::new(),
+                &["target/testing/extern_cmds/nodeps/libnodeps.rlib"],
+            ),
+        );
+        assert!(out.contains("deps not a directory on disk"));
+
+        let out = run(
+            &mut data,
+            add_extern(
+                &mut Vec::new(),
+                &["target/testing/extern_cmds/good/*.rlib", "alias"],
+            ),
+        );
+        assert_eq!(
+            out,
+            "an alias can only be given to one library, `target/testing/extern_cmds/good/*.rlib` matched 3"
+        );
+
+        let out = run(
+            &mut data,
+            add_extern(
+                &mut Vec::new(),
+                &["target/testing/extern_cmds/good/lib*.rlib"],
+            ),
+        );
+        assert_eq!(
+            out,
+            "linked external library `good`\nlinked external library `other`\n"
+        );
+
+        let out = run(
+            &mut data,
+            add_extern(
+                &mut Vec::new(),
+                &["target/testing/extern_cmds/good/libgood.rlib", "g"],
+            ),
+        );
+        assert_eq!(out, "linked external library `good`\n");
+        assert_eq!(data.linking().external_libs.len(), 2); // replaced
+
+        let out = run(&mut data, rm_extern(&mut Vec::new(), &["g"]));
+        assert_eq!(out, "removed external library `g`");
+        let out = run(&mut data, rm_extern(&mut Vec::new(), &["g"]));
+        assert_eq!(out, "no external library named `g`");
+        assert_eq!(data.linking().external_libs.len(), 1);
+
+        let mut buf = Vec::new();
+        add_extern::<(), ()>(&mut buf, &["a.rlib", "not-valid"]);
+        assert_eq!(buf.as_slice(), &b"`not-valid` is not a valid alias\n"[..]);
+    }
+
+    #[test]
+    fn use_module_items_test() {
+        assert_eq!(
+            use_module_items(Path::new("lib")),
+            (
+                "use crate::*;".to_string(),
+                "use crate::_lib_intern_eval as lib_out;".to_string()
+            )
+        );
+        assert_eq!(
+            use_module_items(Path::new("foo/bar")),
+            (
+                "use crate::foo::bar::*;".to_string(),
+                "use crate::foo::bar::_foo_bar_intern_eval as foo_bar_out;".to_string()
+            )
+        );
+    }
+
     #[test]
     fn make_all_parents_test() {
         // only handle parents
@@ -585,18 +2123,18 @@ mod tests {
     #[test]
     fn test_switch_module_priv() {
         let mut buf = Vec::new();
-        switch_module_priv::<(), _>(&[], &mut buf);
+        switch_module_priv::<(), (), _>(&[], &mut buf);
         assert_eq!(
             buf.as_slice(),
             &b"switch expects a path to module argument\n"[..]
         );
 
         buf.clear();
-        switch_module_priv::<(), _>(&["foo"], &mut buf);
+        switch_module_priv::<(), (), _>(&["foo"], &mut buf);
         assert_eq!(buf.as_slice(), &b""[..]);
 
         buf.clear();
-        switch_module_priv::<(), _>(&[""], &mut buf);
+        switch_module_priv::<(), (), _>(&[""], &mut buf);
         println!("{:?}", std::str::from_utf8(&buf));
         assert_eq!(
             buf.as_slice(),
@@ -607,7 +2145,7 @@ mod tests {
     #[test]
     fn test_static_file_interface() {
         let mut buf = Vec::new();
-        add_static_file::<()>(&mut buf, &[]);
+        add_static_file::<(), ()>(&mut buf, &[]);
         println!("{:?}", std::str::from_utf8(&buf));
         assert_eq!(
             buf.as_slice(),
@@ -615,7 +2153,7 @@ mod tests {
         );
 
         buf.clear();
-        rm_static_file::<()>(&mut buf, &[]);
+        rm_static_file::<(), ()>(&mut buf, &[]);
         println!("{:?}", std::str::from_utf8(&buf));
         assert_eq!(
             buf.as_slice(),
@@ -623,6 +2161,10 @@ mod tests {
         );
 
         buf.clear();
-        rm_static_file::<()>(&mut buf, &["what"]);
+        rm_static_file::<(), ()>(&mut buf, &["what"]);
+
+        buf.clear();
+        watch_static_files::<(), ()>(&mut buf, &["maybe"]);
+        assert_eq!(buf.as_slice(), &b"watch expects on or off\n"[..]);
     }
 }
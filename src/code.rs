@@ -35,7 +35,9 @@
 //! let cr = CrateType::parse_str(input).unwrap();
 //!
 //! assert_eq!(&cr.src_line, input);
-//! assert_eq!(&cr.cargo_name, "a-crate");
+//! assert_eq!(&cr.name, "a_crate");
+//! assert_eq!(cr.alias.as_deref(), Some("acrate"));
+//! assert_eq!(&cr.cargo_name, "a_crate");
 //! ```
 //!
 //! [`CrateType`]: CrateType
@@ -45,6 +47,7 @@
 //! [`Statement`]: Statement
 use super::*;
 use crate::linking::LinkingConfiguration;
+use kserd::{Kserd, ToKserd, ToKserdErr};
 use std::{
     borrow::Borrow,
     cmp::Ordering,
@@ -53,6 +56,7 @@ use std::{
     io::{self},
     path::{Path, PathBuf},
 };
+use syn::visit::Visit;
 
 type ReturnRange = std::ops::Range<usize>;
 type ReturnRangeMap<'a> = fxhash::FxHashMap<&'a Path, ReturnRange>;
@@ -232,7 +236,7 @@ pub fn construct_source_code<'a>(
             _ => (),
         }
 
-        contents.push_str("mod ");
+        contents.push_str("pub mod ");
         contents.push_str(
             file.iter()
                 .last()
@@ -355,7 +359,7 @@ fn calc_capacity<'a>(
             _ => (),
         }
 
-        cap += 4; // mod
+        cap += 8; // pub mod
         cap += file
             .iter()
             .last()
@@ -422,21 +426,62 @@ fn append_buffer<S: AsRef<str>>(
     eval_fn_name(mod_path, buf);
     buf.push('(');
     linking_config.construct_fn_args(buf);
-    buf.push_str(") -> kserd::Kserd<'static> {\n"); // 29 len
+    buf.push_str(") -> (kserd::Kserd<'static>, String) {\n"); // 39 len
 
     // add stmts
     let c = src_code.stmts.len();
+    let is_async = has_await(src_code);
+    let is_fallible = has_try(src_code);
+    if is_async && linking_config.async_runtime.is_none() {
+        buf.push_str(BLOCK_ON_FN);
+    }
+    if is_fallible {
+        // match on the result of the fallible statements
+        buf.push_str("match "); // 6 len
+    }
+    if is_async {
+        // run the statements in an async block to completion
+        let block_on = linking_config.async_runtime.as_deref();
+        buf.push_str(block_on.unwrap_or(BLOCK_ON_FN_NAME));
+        buf.push_str("(async move {\n"); // 14 len
+    } else if is_fallible {
+        // run the statements in a closure so `?` can be used
+        buf.push_str("(|| {\n"); // 6 len
+    }
     if c >= 1 {
         // only add statements if more than zero!
         src_code.stmts.iter().enumerate().for_each(|(i, x)| {
             x.assign_let_binding(i, buf);
             buf.push('\n');
         });
-        buf.push_str("kserd::ToKserd::into_kserd(out");
-        buf.push_str(&c.saturating_sub(1).to_string());
-        buf.push_str(").unwrap().into_owned()\n");
+        // return the type name of the last expression alongside the data
+        let out = c.saturating_sub(1).to_string();
+        buf.push_str(
+            "fn type_name_of<T>(_: &T) -> String { std::any::type_name::<T>().to_string() }\n",
+        );
+        buf.push_str("let out_type = type_name_of(&out");
+        buf.push_str(&out);
+        buf.push_str(");\n");
+        if is_fallible {
+            buf.push_str(FALLIBLE_OK);
+        }
+        buf.push_str("(kserd::ToKserd::into_kserd(out");
+        buf.push_str(&out);
+        buf.push_str(").unwrap().into_owned(), out_type)\n");
+        if is_fallible {
+            buf.push_str(")\n");
+        }
     } else {
-        buf.push_str("kserd::Kserd::new_str(\"no statements\")\n");
+        buf.push_str("(kserd::Kserd::new_str(\"no statements\"), String::new())\n");
+    }
+    match (is_async, is_fallible) {
+        (true, true) => buf.push_str("}) {\n"),
+        (true, false) => buf.push_str("})\n"),
+        (false, true) => buf.push_str("})() {\n"),
+        (false, false) => (),
+    }
+    if is_fallible {
+        buf.push_str(FALLIBLE_ARMS);
     }
     buf.push_str("}\n");
 
@@ -474,7 +519,29 @@ fn append_buffer_length<S: AsRef<str>>(
         .sum::<usize>();
 
     // wrap stmts
-    cap += 31 + eval_fn_name_length(mod_path) + 1 + linking_config.construct_fn_args_length() + 29;
+    cap += 31 + eval_fn_name_length(mod_path) + 1 + linking_config.construct_fn_args_length() + 39;
+
+    // run the statements in an async block, or a closure if fallible
+    let (is_async, is_fallible) = (has_await(src_code), has_try(src_code));
+    if is_fallible {
+        cap += 6; // match
+    }
+    if is_async {
+        cap += linking_config
+            .async_runtime
+            .as_ref()
+            .map(|x| x.len())
+            .unwrap_or(BLOCK_ON_FN.len() + BLOCK_ON_FN_NAME.len())
+            + 14; // (async move {\n
+    } else if is_fallible {
+        cap += 6; // (|| {\n
+    }
+    let suffix = match (is_async, is_fallible) {
+        (true, true) => 5 + FALLIBLE_ARMS.len(),  // }) {\n
+        (true, false) => 3,                       // })\n
+        (false, true) => 7 + FALLIBLE_ARMS.len(), // })() {\n
+        (false, false) => 0,
+    };
 
     // add stmts
     let c = src_code.stmts.len();
@@ -485,19 +552,30 @@ fn append_buffer_length<S: AsRef<str>>(
             .enumerate()
             .map(|(i, x)| x.assign_let_binding_length(i) + 1)
             .sum::<usize>();
-        let return_str = 30 // kserd::ToKserd::into_kserd(out
-            + c.saturating_sub(1).to_string().len()
-            + 24; // ).unwrap().into_owned()\n
+        let out_len = c.saturating_sub(1).to_string().len();
+        let type_str = 79 // fn type_name_of<T>...\n
+            + 32 // let out_type = type_name_of(&out
+            + out_len
+            + 3; // );\n
+        let return_str = 31 // (kserd::ToKserd::into_kserd(out
+            + out_len
+            + 35; // ).unwrap().into_owned(), out_type)\n
+        let ok_str = if is_fallible {
+            FALLIBLE_OK.len() + 2 // )\n
+        } else {
+            0
+        };
 
+        let start = cap + stmts + type_str + if is_fallible { FALLIBLE_OK.len() } else { 0 };
         (
-            stmts + return_str,
-            cap + stmts..cap + stmts + return_str - 1,
+            stmts + type_str + return_str + ok_str,
+            start..start + return_str - 1,
         )
     } else {
-        // kserd::Kserd::new_str("no statements")\n
-        (39, cap..cap + 38)
+        // (kserd::Kserd::new_str("no statements"), String::new())\n
+        (56, cap..cap + 55)
     };
-    cap += add + 2; // }\n
+    cap += add + suffix + 2; // }\n
 
     // add items
     cap += src_code
@@ -510,6 +588,165 @@ fn append_buffer_length<S: AsRef<str>>(
     (cap, rng)
 }
 
+/// The range of the statements of input `index` in the source code built by
+/// [`construct_source_code`], including the trailing new line.
+///
+/// `return_range` is the range of the module's return expression, from the map returned by
+/// [`construct_source_code`]. Returns `None` if there is no input `index`.
+pub(crate) fn input_range(
+    src_code: &SourceCode,
+    index: usize,
+    return_range: &ReturnRange,
+) -> Option<ReturnRange> {
+    let c = src_code.stmts.len();
+    if index >= c {
+        return None;
+    }
+
+    let len = |i: usize| src_code.stmts[i].assign_let_binding_length(i) + 1;
+    let type_str = 79 + 32 + (c - 1).to_string().len() + 3; // see append_buffer_length
+    let ok_str = if has_try(src_code) {
+        FALLIBLE_OK.len()
+    } else {
+        0
+    };
+
+    let end = return_range.start - ok_str - type_str - (index + 1..c).map(len).sum::<usize>();
+    Some(end - len(index)..end)
+}
+
+/// The name of the bundled executor function.
+const BLOCK_ON_FN_NAME: &str = "__papyrus_block_on";
+
+/// A small single-threaded executor, used to run async code when no runtime is configured.
+///
+/// Parking the thread is avoided as it uses thread locals, which can not outlive the library.
+const BLOCK_ON_FN: &str = "fn __papyrus_block_on<F: std::future::Future>(f: F) -> F::Output { \
+use std::sync::{Arc, Condvar, Mutex}; \
+struct W(Mutex<bool>, Condvar); \
+impl std::task::Wake for W { fn wake(self: Arc<Self>) { *self.0.lock().unwrap() = true; self.1.notify_one(); } } \
+let w = Arc::new(W(Mutex::new(false), Condvar::new())); \
+let waker = std::task::Waker::from(w.clone()); \
+let mut cx = std::task::Context::from_waker(&waker); \
+let mut f = Box::pin(f); \
+loop { \
+if let std::task::Poll::Ready(x) = std::future::Future::poll(f.as_mut(), &mut cx) { return x; } \
+let mut woken = w.0.lock().unwrap(); \
+while !*woken { woken = w.1.wait(woken).unwrap(); } \
+*woken = false; \
+} }\n";
+
+/// Source code containing `.await` is run in an async block.
+fn has_await(src_code: &SourceCode) -> bool {
+    let mut finder = FindAwait(false);
+    if let Some(block) = stmts_block(src_code) {
+        finder.visit_block(&block);
+    }
+    finder.0
+}
+
+/// The statements parsed as a block, `None` if there are no statements or they do not parse.
+fn stmts_block(src_code: &SourceCode) -> Option<syn::Block> {
+    if src_code.stmts.is_empty() {
+        return None;
+    }
+
+    let code = src_code
+        .stmts
+        .iter()
+        .flat_map(|grp| grp.0.iter())
+        .map(|stmt| stmt.expr.as_str())
+        .collect::<Vec<_>>()
+        .join(";\n");
+    syn::parse_str(&format!("{{ {} }}", code)).ok()
+}
+
+/// Finds `.await` expressions in the statements themselves, not within closures, async blocks or
+/// items. Macro arguments are inspected if they are comma separated expressions.
+struct FindAwait(bool);
+
+impl<'ast> Visit<'ast> for FindAwait {
+    fn visit_expr_await(&mut self, _: &'ast syn::ExprAwait) {
+        self.0 = true;
+    }
+
+    fn visit_expr_async(&mut self, _: &'ast syn::ExprAsync) {}
+
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        for expr in macro_args(mac) {
+            self.visit_expr(&expr);
+        }
+    }
+}
+
+/// Finds `?` expressions in the statements themselves, not within closures, async blocks, try
+/// blocks or items. Macro arguments are inspected if they are comma separated expressions.
+struct FindTry(bool);
+
+impl<'ast> Visit<'ast> for FindTry {
+    fn visit_expr_try(&mut self, _: &'ast syn::ExprTry) {
+        self.0 = true;
+    }
+
+    fn visit_expr_async(&mut self, _: &'ast syn::ExprAsync) {}
+
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+
+    fn visit_expr_try_block(&mut self, _: &'ast syn::ExprTryBlock) {}
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        for expr in macro_args(mac) {
+            self.visit_expr(&expr);
+        }
+    }
+}
+
+/// The arguments of a macro invocation, if they parse as comma separated expressions, such as
+/// `println!("{}", x)`.
+fn macro_args(mac: &syn::Macro) -> Vec<syn::Expr> {
+    use syn::{punctuated::Punctuated, Expr, Token};
+
+    mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        .map(|args| args.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Source code containing `?` is run in a closure returning a `Result`.
+fn has_try(src_code: &SourceCode) -> bool {
+    let mut finder = FindTry(false);
+    if let Some(block) = stmts_block(src_code) {
+        finder.visit_block(&block);
+    }
+    finder.0
+}
+
+macro_rules! err_type_name {
+    () => {
+        "<error>"
+    };
+}
+
+/// The type name returned alongside the error message if fallible statements return an `Err`.
+pub(crate) const ERR_TYPE_NAME: &str = err_type_name!();
+
+/// Wraps the return value of fallible statements.
+const FALLIBLE_OK: &str = "Ok::<_, Box<dyn std::error::Error>>(";
+
+/// Converts the result of fallible statements into the returned data and type name.
+const FALLIBLE_ARMS: &str = concat!(
+    "Ok(x) => x,\n",
+    "Err(e) => (kserd::Kserd::new_string(e.to_string()), String::from(\"",
+    err_type_name!(),
+    "\")),\n",
+    "}\n"
+);
+
 /// A single item.
 ///
 /// Wraps as `(content, top_placement)`.
@@ -534,44 +771,52 @@ pub struct Statement {
 /// # Examples
 /// ```rust
 /// # use papyrus::code::CrateType;
-/// let input = "extern crate a_crate as acrate;";
+/// let input = "#[macro_use]\nextern crate a_crate as acrate;";
 /// let cr = CrateType::parse_str(input).unwrap();
 /// assert_eq!(&cr.src_line, input);
-/// assert_eq!(&cr.cargo_name, "a-crate");
+/// assert_eq!(&cr.name, "a_crate");
+/// assert_eq!(cr.alias.as_deref(), Some("acrate"));
+/// assert_eq!(&cr.cargo_name, "a_crate");
 /// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct CrateType {
     /// The source line which adds the crates.
     /// This is usually `extern crate crate_name;` or could be `extern crate crate_name as alias;`
     pub src_line: String,
+    /// The name of the crate as used in Rust, ie `crate_name`.
+    pub name: String,
+    /// The alias of the crate, if it is renamed with `as alias`.
+    pub alias: Option<String>,
     /// The name to use in cargo.
-    /// Usually `crate_name` will turn into `crate-name`. The default behaviour is to replace `_` with a dash (`-`).
+    ///
+    /// Defaults to the crate name. Packages whose name differs from the crate name, such as
+    /// `crate-name`, can be mapped using
+    /// [`LinkingConfiguration::crate_names`](crate::linking::LinkingConfiguration).
     pub cargo_name: String,
 }
 
 impl CrateType {
     /// Parses a string to return the `CrateType`.
+    ///
+    /// The string must be a single `extern crate` item, which can include attributes and an alias.
     pub fn parse_str(string: &str) -> Result<Self, &'static str> {
-        let line = string
-            .replace(';', "")
-            .replace('_', "-")
-            .trim()
-            .split('\n')
-            .next()
-            .expect("string should have one line")
-            .to_string();
-        if line.contains("extern crate ") {
-            Ok(CrateType {
-                src_line: string.to_string(),
-                cargo_name: line
-                    .split(' ')
-                    .nth(2)
-                    .expect("should always have trailing item")
-                    .to_string(),
-            })
-        } else {
-            Err("line needs `extern crate NAME;`")
+        use syn::ext::IdentExt;
+
+        let err = "line needs `extern crate NAME;`";
+
+        let item = syn::parse_str::<syn::ItemExternCrate>(string.trim()).map_err(|_| err)?;
+
+        let name = item.ident.unraw().to_string();
+        if name == "self" {
+            return Err(err);
         }
+
+        Ok(CrateType {
+            src_line: string.to_string(),
+            cargo_name: name.clone(),
+            name,
+            alias: item.rename.map(|(_, alias)| alias.unraw().to_string()),
+        })
     }
 }
 
@@ -614,6 +859,25 @@ impl Borrow<Path> for StaticFile {
     }
 }
 
+impl StaticFile {
+    /// Validates the path and parses the referenced crates out of the code.
+    /// Returns the static file along with the remaining code, which is written to disk.
+    pub(crate) fn parse(path: PathBuf, code: &str) -> Result<(Self, &str), AddingStaticFileError> {
+        validate_static_file_path(&path).map_err(AddingStaticFileError::InvalidPath)?;
+
+        let hash: [u8; 32] = blake3::hash(code.as_bytes()).into();
+        let (code, crates) = parse_crates_in_file(code);
+
+        let file = StaticFile {
+            path,
+            codehash: Box::new(hash),
+            crates,
+        };
+
+        Ok((file, code))
+    }
+}
+
 /// Errors around adding a static file.
 #[derive(Debug)]
 pub enum AddingStaticFileError {
@@ -713,7 +977,8 @@ fn valid_identifier(s: &str) -> Result<(), &'static str> {
 
 /// Parse a code string for any crate references.
 ///
-/// Returns the code slice _without_ the crate references.
+/// The crate references must be at the beginning of the code, and can include attributes and
+/// comments. Returns the code slice _without_ the crate references.
 ///
 /// # Example
 /// ```rust
@@ -732,16 +997,24 @@ pub fn parse_crates_in_file(s: &str) -> (&str, Vec<CrateType>) {
     for (idx, ch) in s.char_indices() {
         let end = idx + ch.len_utf8();
         if ch == ';' {
+            let chunk = &s[start..end];
+            let t = chunk.trim_start();
+            if !["extern", "#", "//", "/*"].iter().any(|x| t.starts_with(x)) {
+                break; // can not be a crate reference, stop search
+            }
+
             // check if can parse as crate
-            match CrateType::parse_str(&s[start..end]) {
+            match CrateType::parse_str(chunk) {
                 Ok(c) => {
                     v.push(c);
                     start = end;
                 }
-                Err(_) => {
-                    // stop search
-                    break;
-                }
+                Err(_) => match syn::parse_str::<syn::File>(chunk) {
+                    // other code, stop search
+                    Ok(file) if !file.items.is_empty() => break,
+                    // the semi-colon is within a comment or attribute, keep going
+                    _ => (),
+                },
             }
         }
     }
@@ -784,6 +1057,165 @@ pub fn static_file_mod_name(path: &Path) -> Option<&str> {
     }
 }
 
+// ###### SERIALISATION ##################################################################
+impl ToKserd<'static> for SourceCode {
+    fn into_kserd(self) -> Result<Kserd<'static>, ToKserdErr> {
+        Ok(Kserd::new_cntr(vec![
+            ("items", self.items.into_kserd()?),
+            ("stmts", self.stmts.into_kserd()?),
+            ("crates", self.crates.into_kserd()?),
+        ])?)
+    }
+}
+
+impl SourceCode {
+    /// Reads the `SourceCode` from a `Kserd` that was produced through [`ToKserd`].
+    ///
+    /// Returns `None` if the structure does not match.
+    pub fn from_kserd(kserd: &Kserd) -> Option<Self> {
+        let cntr = kserd.cntr()?;
+
+        let items = cntr
+            .get_seq("items")?
+            .iter()
+            .map(|item| {
+                let tuple = item.tuple()?;
+                match tuple.as_slice() {
+                    [code, top] => Some((code.str()?.to_string(), top.bool()?)),
+                    _ => None,
+                }
+            })
+            .collect::<Option<_>>()?;
+
+        let stmts = cntr
+            .get_seq("stmts")?
+            .iter()
+            .map(StmtGrp::from_kserd)
+            .collect::<Option<_>>()?;
+
+        let crates = cntr
+            .get_seq("crates")?
+            .iter()
+            .map(CrateType::from_kserd)
+            .collect::<Option<_>>()?;
+
+        Some(SourceCode {
+            items,
+            stmts,
+            crates,
+        })
+    }
+}
+
+impl ToKserd<'static> for StmtGrp {
+    fn into_kserd(self) -> Result<Kserd<'static>, ToKserdErr> {
+        self.0.into_kserd()
+    }
+}
+
+impl StmtGrp {
+    /// Reads the `StmtGrp` from a `Kserd` that was produced through [`ToKserd`].
+    ///
+    /// Returns `None` if the structure does not match.
+    pub fn from_kserd(kserd: &Kserd) -> Option<Self> {
+        kserd
+            .seq()?
+            .iter()
+            .map(Statement::from_kserd)
+            .collect::<Option<_>>()
+            .map(StmtGrp)
+    }
+}
+
+impl ToKserd<'static> for Statement {
+    fn into_kserd(self) -> Result<Kserd<'static>, ToKserdErr> {
+        Ok(Kserd::new_cntr(vec![
+            ("expr", Kserd::new_string(self.expr)),
+            ("semi", Kserd::new_bool(self.semi)),
+        ])?)
+    }
+}
+
+impl Statement {
+    /// Reads the `Statement` from a `Kserd` that was produced through [`ToKserd`].
+    ///
+    /// Returns `None` if the structure does not match.
+    pub fn from_kserd(kserd: &Kserd) -> Option<Self> {
+        let cntr = kserd.cntr()?;
+        Some(Statement {
+            expr: cntr.get_str("expr")?.to_string(),
+            semi: cntr.get_bool("semi")?,
+        })
+    }
+}
+
+impl ToKserd<'static> for CrateType {
+    fn into_kserd(self) -> Result<Kserd<'static>, ToKserdErr> {
+        Ok(Kserd::new_cntr(vec![
+            ("src_line", Kserd::new_string(self.src_line)),
+            ("name", Kserd::new_string(self.name)),
+            ("alias", self.alias.into_kserd()?),
+            ("cargo_name", Kserd::new_string(self.cargo_name)),
+        ])?)
+    }
+}
+
+impl CrateType {
+    /// Reads the `CrateType` from a `Kserd` that was produced through [`ToKserd`].
+    ///
+    /// Returns `None` if the structure does not match.
+    pub fn from_kserd(kserd: &Kserd) -> Option<Self> {
+        let cntr = kserd.cntr()?;
+        Some(CrateType {
+            src_line: cntr.get_str("src_line")?.to_string(),
+            name: cntr.get_str("name")?.to_string(),
+            alias: cntr.get_str("alias").map(String::from),
+            cargo_name: cntr.get_str("cargo_name")?.to_string(),
+        })
+    }
+}
+
+impl ToKserd<'static> for StaticFile {
+    fn into_kserd(self) -> Result<Kserd<'static>, ToKserdErr> {
+        Ok(Kserd::new_cntr(vec![
+            (
+                "path",
+                Kserd::new_string(self.path.to_string_lossy().into_owned()),
+            ),
+            ("codehash", Kserd::new_barrv(self.codehash.to_vec())),
+            ("crates", self.crates.into_kserd()?),
+        ])?)
+    }
+}
+
+impl StaticFile {
+    /// Reads the `StaticFile` from a `Kserd` that was produced through [`ToKserd`].
+    ///
+    /// Returns `None` if the structure does not match.
+    pub fn from_kserd(kserd: &Kserd) -> Option<Self> {
+        let cntr = kserd.cntr()?;
+
+        let mut codehash = Box::new([0; 32]);
+        let hash = cntr.get_barr("codehash")?;
+        if hash.len() != codehash.len() {
+            return None;
+        }
+        codehash.copy_from_slice(hash);
+
+        let crates = cntr
+            .get_seq("crates")?
+            .iter()
+            .map(CrateType::from_kserd)
+            .collect::<Option<_>>()?;
+
+        Some(StaticFile {
+            path: PathBuf::from(cntr.get_str("path")?),
+            codehash,
+            crates,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -820,45 +1252,66 @@ mod tests {
         let c = CrateType::parse_str("extern crate ;");
         assert_eq!(c, err);
 
+        let c = CrateType::parse_str("extern crate some-lib;");
+        assert_eq!(c, err);
+
+        let c = CrateType::parse_str("extern crate some lib;");
+        assert_eq!(c, err);
+
+        let c = CrateType::parse_str("extern crate self as name;");
+        assert_eq!(c, err);
+
         let s = String::from("extern crate somelib;");
         let c = CrateType::parse_str(&s);
         assert_eq!(
             c,
             Ok(CrateType {
                 src_line: s,
+                name: String::from("somelib"),
+                alias: None,
                 cargo_name: String::from("somelib"),
             })
         );
 
-        let s = String::from("extern crate some-lib;");
+        let s = String::from("extern crate some_lib;");
         let c = CrateType::parse_str(&s);
         assert_eq!(
             c,
             Ok(CrateType {
                 src_line: s,
-                cargo_name: String::from("some-lib"),
+                name: String::from("some_lib"),
+                alias: None,
+                cargo_name: String::from("some_lib"),
             })
         );
 
-        let s = String::from("extern crate some lib;");
+        let s = String::from("/// docs\n#[macro_use]\nextern crate\n    some_lib\n    as lib;");
         let c = CrateType::parse_str(&s);
         assert_eq!(
             c,
             Ok(CrateType {
                 src_line: s,
-                cargo_name: String::from("some"),
+                name: String::from("some_lib"),
+                alias: Some(String::from("lib")),
+                cargo_name: String::from("some_lib"),
             })
         );
+    }
 
-        let s = String::from("extern crate some_lib;");
-        let c = CrateType::parse_str(&s);
-        assert_eq!(
-            c,
-            Ok(CrateType {
-                src_line: s,
-                cargo_name: String::from("some-lib"),
-            })
+    #[test]
+    fn parse_crates_in_file_test() {
+        let (slice, crates) = parse_crates_in_file(
+            "// a comment; with a semi\n#[macro_use]\nextern crate serde_json as json;\nextern crate rand;\nfn a() {}",
         );
+        assert_eq!(slice, "\nfn a() {}");
+        assert_eq!(crates.len(), 2);
+        assert_eq!(&crates[0].name, "serde_json");
+        assert_eq!(crates[0].alias.as_deref(), Some("json"));
+        assert_eq!(&crates[1].name, "rand");
+
+        let (slice, crates) = parse_crates_in_file("let a = 1; extern crate rand;");
+        assert_eq!(slice, "let a = 1; extern crate rand;");
+        assert!(crates.is_empty());
     }
 
     #[test]
@@ -924,14 +1377,17 @@ mod tests {
             append_buffer_length(&src_code, &mod_path, &linking_config, &StaticFiles::new());
 
         let ans = r##"#[no_mangle]
-pub extern "C" fn _intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 "##;
         assert_eq!(&s, ans);
         assert_eq!(len, ans.len());
-        assert_eq!(rng, 73..111);
-        assert_eq!(&ans[rng], r#"kserd::Kserd::new_str("no statements")"#);
+        assert_eq!(rng, 83..138);
+        assert_eq!(
+            &ans[rng],
+            r#"(kserd::Kserd::new_str("no statements"), String::new())"#
+        );
 
         // alter mod path
         let mod_path = ["some".to_string(), "path".to_string()];
@@ -948,14 +1404,17 @@ kserd::Kserd::new_str("no statements")
             append_buffer_length(&src_code, &mod_path, &linking_config, &StaticFiles::new());
 
         let ans = r##"#[no_mangle]
-pub extern "C" fn _some_path_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _some_path_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 "##;
         assert_eq!(&s, ans);
         assert_eq!(len, ans.len());
-        assert_eq!(rng, 83..121);
-        assert_eq!(&ans[rng], r#"kserd::Kserd::new_str("no statements")"#);
+        assert_eq!(rng, 93..148);
+        assert_eq!(
+            &ans[rng],
+            r#"(kserd::Kserd::new_str("no statements"), String::new())"#
+        );
 
         // alter the linking config
         let mut linking_config = LinkingConfiguration {
@@ -975,14 +1434,17 @@ kserd::Kserd::new_str("no statements")
             append_buffer_length(&src_code, &mod_path, &linking_config, &StaticFiles::new());
 
         let ans = r##"#[no_mangle]
-pub extern "C" fn _some_path_intern_eval(app_data: &String) -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _some_path_intern_eval(app_data: &String) -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 "##;
         assert_eq!(&s, ans);
         assert_eq!(len, ans.len());
-        assert_eq!(rng, 100..138);
-        assert_eq!(&ans[rng], r#"kserd::Kserd::new_str("no statements")"#);
+        assert_eq!(rng, 110..165);
+        assert_eq!(
+            &ans[rng],
+            r#"(kserd::Kserd::new_str("no statements"), String::new())"#
+        );
 
         // add an item and new input
         src_code.items.push(("fn a() {}".to_string(), false));
@@ -1000,16 +1462,19 @@ kserd::Kserd::new_str("no statements")
             append_buffer_length(&src_code, &mod_path, &linking_config, &StaticFiles::new());
 
         let ans = r##"#[no_mangle]
-pub extern "C" fn _some_path_intern_eval(app_data: &String) -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _some_path_intern_eval(app_data: &String) -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 fn a() {}
 fn b() {}
 "##;
         assert_eq!(&s, ans);
         assert_eq!(len, ans.len());
-        assert_eq!(rng, 100..138);
-        assert_eq!(&ans[rng], r#"kserd::Kserd::new_str("no statements")"#);
+        assert_eq!(rng, 110..165);
+        assert_eq!(
+            &ans[rng],
+            r#"(kserd::Kserd::new_str("no statements"), String::new())"#
+        );
 
         // add stmts
         src_code.stmts.push(StmtGrp(vec![
@@ -1057,23 +1522,197 @@ fn b() {}
         let ans = r##"#![feature(UP_TOP)]
 some-injected-persistent-code
 #[no_mangle]
-pub extern "C" fn _some_path_intern_eval(app_data: &String) -> kserd::Kserd<'static> {
+pub extern "C" fn _some_path_intern_eval(app_data: &String) -> (kserd::Kserd<'static>, String) {
 let a = 1;
 let out0 = b;
 let c = 2;
 let out1 = d;
-kserd::ToKserd::into_kserd(out1).unwrap().into_owned()
+fn type_name_of<T>(_: &T) -> String { std::any::type_name::<T>().to_string() }
+let out_type = type_name_of(&out1);
+(kserd::ToKserd::into_kserd(out1).unwrap().into_owned(), out_type)
 }
 fn a() {}
 fn b() {}
 "##;
         assert_eq!(&s, ans);
         assert_eq!(len, ans.len());
-        assert_eq!(rng, 200..254);
+        assert_eq!(rng, 325..391);
         assert_eq!(
             &ans[rng],
-            "kserd::ToKserd::into_kserd(out1).unwrap().into_owned()"
+            "(kserd::ToKserd::into_kserd(out1).unwrap().into_owned(), out_type)"
+        );
+    }
+
+    #[test]
+    fn has_await_test() {
+        let src = |exprs: &[&str]| {
+            let mut src_code = SourceCode::default();
+            src_code.stmts.push(StmtGrp(
+                exprs
+                    .iter()
+                    .map(|x| Statement {
+                        expr: x.to_string(),
+                        semi: false,
+                    })
+                    .collect(),
+            ));
+            src_code
+        };
+
+        assert!(has_await(&src(&["f().await"])));
+        assert!(has_await(&src(&["let a = f().await", "a"])));
+        assert!(has_await(&src(&["println!(\"{}\", f().await)"])));
+
+        assert!(!has_await(&src(&["\".await\""])));
+        assert!(!has_await(&src(&["// .await", "1"])));
+        assert!(!has_await(&src(&["async { f().await }"])));
+        assert!(!has_await(&src(&["fn a() { f().await }"])));
+        assert!(!has_await(&SourceCode::default()));
+    }
+
+    #[test]
+    fn has_try_test() {
+        let src = |expr: &str| {
+            let mut src_code = SourceCode::default();
+            src_code.stmts.push(StmtGrp(vec![Statement {
+                expr: expr.to_string(),
+                semi: false,
+            }]));
+            src_code
+        };
+
+        assert!(has_try(&src("\"1\".parse::<i32>()?")));
+        assert!(has_try(&src("dbg!(\"1\".parse::<i32>()?)")));
+
+        assert!(!has_try(&src("println!(\"{:?}\", x)")));
+        assert!(!has_try(&src("'?'")));
+        assert!(!has_try(&src("\"what?\"")));
+        assert!(!has_try(&src("|| -> Result<i32, ()> { Ok(f()?) }")));
+    }
+
+    #[test]
+    fn construct_async_test() {
+        let mut src_code = SourceCode::default();
+        src_code.stmts.push(StmtGrp(vec![Statement {
+            expr: "async { 1 }.await".to_string(),
+            semi: false,
+        }]));
+        let mod_path: &[&str] = &[];
+        let mut linking_config = LinkingConfiguration::default();
+
+        let build = |linking_config: &LinkingConfiguration| {
+            let mut s = String::new();
+            append_buffer(
+                &src_code,
+                mod_path,
+                linking_config,
+                &StaticFiles::new(),
+                &mut s,
+            );
+            let (len, rng) =
+                append_buffer_length(&src_code, mod_path, linking_config, &StaticFiles::new());
+            assert_eq!(len, s.len());
+            assert_eq!(
+                &s[rng],
+                "(kserd::ToKserd::into_kserd(out0).unwrap().into_owned(), out_type)"
+            );
+            s
+        };
+
+        let s = build(&linking_config);
+        assert!(s.contains(BLOCK_ON_FN));
+        assert!(s.contains("__papyrus_block_on(async move {\nlet out0 = async { 1 }.await;\n"));
+        assert!(s.ends_with("out_type)\n})\n}\n"));
+
+        linking_config.async_runtime =
+            Some("tokio::runtime::Runtime::new().unwrap().block_on".to_string());
+        let s = build(&linking_config);
+        assert!(!s.contains(BLOCK_ON_FN));
+        assert!(s.contains("tokio::runtime::Runtime::new().unwrap().block_on(async move {\n"));
+    }
+
+    #[test]
+    fn construct_fallible_test() {
+        let mut src_code = SourceCode::default();
+        src_code.stmts.push(StmtGrp(vec![Statement {
+            expr: "\"1\".parse::<i32>()?".to_string(),
+            semi: false,
+        }]));
+        let mod_path: &[&str] = &[];
+
+        let build = |src_code: &SourceCode, out: usize| {
+            let linking_config = LinkingConfiguration::default();
+            let mut s = String::new();
+            append_buffer(
+                src_code,
+                mod_path,
+                &linking_config,
+                &StaticFiles::new(),
+                &mut s,
+            );
+            let (len, rng) =
+                append_buffer_length(src_code, mod_path, &linking_config, &StaticFiles::new());
+            assert_eq!(len, s.len());
+            assert_eq!(
+                &s[rng],
+                format!(
+                    "(kserd::ToKserd::into_kserd(out{}).unwrap().into_owned(), out_type)",
+                    out
+                )
+            );
+            s
+        };
+
+        let s = build(&src_code, 0);
+        assert!(s.contains("match (|| {\nlet out0 = \"1\".parse::<i32>()?;\n"));
+        assert!(s.contains(FALLIBLE_OK));
+        assert!(s.ends_with(&format!("out_type)\n)\n}})() {{\n{}}}\n", FALLIBLE_ARMS)));
+        assert!(FALLIBLE_ARMS.contains(ERR_TYPE_NAME));
+
+        // async and fallible
+        src_code.stmts.push(StmtGrp(vec![Statement {
+            expr: "async { 1 }.await".to_string(),
+            semi: false,
+        }]));
+        let s = build(&src_code, 1);
+        assert!(s.contains("match __papyrus_block_on(async move {\n"));
+        assert!(s.ends_with(&format!("out_type)\n)\n}}) {{\n{}}}\n", FALLIBLE_ARMS)));
+    }
+
+    #[test]
+    fn input_range_test() {
+        let stmt = |expr: &str| Statement {
+            expr: expr.to_string(),
+            semi: expr.starts_with("let "),
+        };
+        let mut lib = SourceCode::default();
+        lib.stmts.push(StmtGrp(vec![stmt("let a = 1"), stmt("a")]));
+        for i in 0..10 {
+            lib.stmts.push(StmtGrp(vec![stmt(&format!("a + {}", i))]));
+        }
+        let mut foo = SourceCode::default();
+        foo.stmts.push(StmtGrp(vec![stmt("\"1\".parse::<i32>()?")]));
+        foo.stmts.push(StmtGrp(vec![stmt("2")]));
+
+        let map = vec![("lib".into(), lib.clone()), ("foo".into(), foo.clone())]
+            .into_iter()
+            .collect();
+        let (s, map) =
+            construct_source_code(&map, &LinkingConfiguration::default(), &StaticFiles::new());
+
+        let rng = |path: &str, src: &SourceCode, index: usize| {
+            input_range(src, index, &map[Path::new(path)]).map(|x| &s[x])
+        };
+
+        assert_eq!(rng("lib", &lib, 0), Some("let a = 1;\nlet out0 = a;\n"));
+        assert_eq!(rng("lib", &lib, 1), Some("let out1 = a + 0;\n"));
+        assert_eq!(rng("lib", &lib, 10), Some("let out10 = a + 9;\n"));
+        assert_eq!(rng("lib", &lib, 11), None);
+        assert_eq!(
+            rng("foo", &foo, 0),
+            Some("let out0 = \"1\".parse::<i32>()?;\n")
         );
+        assert_eq!(rng("foo", &foo, 1), Some("let out1 = 2;\n"));
     }
 
     #[test]
@@ -1096,39 +1735,39 @@ fn b() {}
         let (s, map) = construct_source_code(&map, &linking, &StaticFiles::new());
 
         let ans = r##"#[no_mangle]
-pub extern "C" fn _lib_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _lib_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod foo {
+pub mod foo {
 #[no_mangle]
-pub extern "C" fn _foo_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _foo_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod bar {
+pub mod bar {
 #[no_mangle]
-pub extern "C" fn _foo_bar_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _foo_bar_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }}
-mod test {
+pub mod test {
 #[no_mangle]
-pub extern "C" fn _test_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod inner {
+pub mod inner {
 #[no_mangle]
-pub extern "C" fn _test_inner_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_inner_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }
-mod inner2 {
+pub mod inner2 {
 #[no_mangle]
-pub extern "C" fn _test_inner2_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_inner2_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }}"##;
 
-        let return_stmt = r#"kserd::Kserd::new_str("no statements")"#;
+        let return_stmt = r#"(kserd::Kserd::new_str("no statements"), String::new())"#;
         assert_eq!(&s, ans);
         assert_eq!(
             &ans[map.get(Path::new("lib")).unwrap().clone()],
@@ -1212,8 +1851,8 @@ kserd::Kserd::new_str("no statements")
 
         let ans = r##"Up Top
 #[no_mangle]
-pub extern "C" fn _lib_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _lib_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 Test1
 "##;
@@ -1335,49 +1974,49 @@ Test1
         let ans = r##"mod bar2;
 mod foo2;
 #[no_mangle]
-pub extern "C" fn _lib_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _lib_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod foo {
+pub mod foo {
 use crate::bar2;
 use crate::foo2;
 #[no_mangle]
-pub extern "C" fn _foo_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _foo_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod bar {
+pub mod bar {
 use crate::bar2;
 use crate::foo2;
 #[no_mangle]
-pub extern "C" fn _foo_bar_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _foo_bar_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }}
-mod test {
+pub mod test {
 use crate::bar2;
 use crate::foo2;
 #[no_mangle]
-pub extern "C" fn _test_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
-mod inner {
+pub mod inner {
 use crate::bar2;
 use crate::foo2;
 #[no_mangle]
-pub extern "C" fn _test_inner_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_inner_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }
-mod inner2 {
+pub mod inner2 {
 use crate::bar2;
 use crate::foo2;
 #[no_mangle]
-pub extern "C" fn _test_inner2_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _test_inner2_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 }}"##;
 
-        let return_stmt = r#"kserd::Kserd::new_str("no statements")"#;
+        let return_stmt = r#"(kserd::Kserd::new_str("no statements"), String::new())"#;
         println!("{}", s);
         assert_eq!(&s, ans);
         assert_eq!(
@@ -1481,12 +2120,12 @@ kserd::Kserd::new_str("no statements")
         let ans = r##"mod bar2;
 mod foo2;
 #[no_mangle]
-pub extern "C" fn _lib_intern_eval() -> kserd::Kserd<'static> {
-kserd::Kserd::new_str("no statements")
+pub extern "C" fn _lib_intern_eval() -> (kserd::Kserd<'static>, String) {
+(kserd::Kserd::new_str("no statements"), String::new())
 }
 "##;
 
-        let return_stmt = r#"kserd::Kserd::new_str("no statements")"#;
+        let return_stmt = r#"(kserd::Kserd::new_str("no statements"), String::new())"#;
         println!("{}", s);
         assert_eq!(&s, ans);
         assert_eq!(
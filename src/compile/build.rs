@@ -24,18 +24,7 @@ where
         lib_file.join(format!("lib{}.so", LIBRARY_NAME))
     };
 
-    let mut args = vec!["rustc".to_owned(), "--".to_owned(), "-Awarnings".to_owned()];
-
-    for external in linking_config.external_libs.iter() {
-        args.push("-L".to_owned());
-        args.push(format!("dependency={}", external.deps_path().display()));
-        args.push("--extern".to_owned());
-        args.push(format!(
-            "{}={}",
-            external.lib_name(),
-            external.lib_path().display()
-        ));
-    }
+    let args = rustc_args(linking_config);
 
     let mut child = Command::new("cargo")
         .current_dir(compile_dir)
@@ -69,6 +58,78 @@ where
     }
 }
 
+/// Macro expand the library in the compile directory, returning the expanded source code.
+///
+/// Uses `-Zunpretty=expanded`, which requires a nightly compiler. If the default toolchain is not
+/// nightly, the `nightly` toolchain is used through `rustup` (`cargo +nightly`). An error is
+/// returned if neither is available.
+pub fn expand<P: AsRef<Path>>(
+    compile_dir: P,
+    linking_config: &crate::linking::LinkingConfiguration,
+) -> Result<String, CompilationError> {
+    let compile_dir = compile_dir.as_ref();
+
+    let mut args = rustc_args(linking_config);
+    args.push("-Zunpretty=expanded".to_owned());
+
+    if !is_nightly(compile_dir, None) {
+        if is_nightly(compile_dir, Some("+nightly")) {
+            args.insert(0, "+nightly".to_owned());
+        } else {
+            return Err(CompilationError::CompileError(
+                "expanding macros requires a nightly compiler, install one with \
+                 `rustup toolchain install nightly`"
+                    .to_owned(),
+            ));
+        }
+    }
+
+    let output = Command::new("cargo")
+        .current_dir(compile_dir)
+        .args(&args)
+        .output()
+        .map_err(|_| CompilationError::NoBuildCommand)?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout)
+            .map_err(|e| CompilationError::IOError(io::Error::new(io::ErrorKind::InvalidData, e)))
+    } else {
+        Err(CompilationError::CompileError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ))
+    }
+}
+
+/// Whether `rustc`, run with the optional toolchain argument such as `+nightly`, is a nightly
+/// compiler.
+fn is_nightly(dir: &Path, toolchain: Option<&str>) -> bool {
+    Command::new("rustc")
+        .current_dir(dir)
+        .args(toolchain)
+        .arg("--version")
+        .output()
+        .map(|x| x.status.success() && String::from_utf8_lossy(&x.stdout).contains("nightly"))
+        .unwrap_or(false)
+}
+
+/// The `cargo rustc` arguments, linking the external libraries.
+fn rustc_args(linking_config: &crate::linking::LinkingConfiguration) -> Vec<String> {
+    let mut args = vec!["rustc".to_owned(), "--".to_owned(), "-Awarnings".to_owned()];
+
+    for external in linking_config.external_libs.iter() {
+        args.push("-L".to_owned());
+        args.push(format!("dependency={}", external.deps_path().display()));
+        args.push("--extern".to_owned());
+        args.push(format!(
+            "{}={}",
+            external.lib_name(),
+            external.lib_path().display()
+        ));
+    }
+
+    args
+}
+
 /// Function to rename the output library file and remove the associated dependency.
 ///
 /// In relation to [#44](https://github.com/kurtlawrence/papyrus/issues/44), loading a library will
@@ -27,7 +27,7 @@ where
         .iter()
         .flat_map(|kvp| kvp.1.crates.iter())
         .chain(static_files.iter().flat_map(|x| x.crates.iter()));
-    let crates = dedup_crates(crates);
+    let crates = dedup_crates(crates, linking_config);
 
     // write cargo toml contents
     create_file_and_dir(compile_dir.join("Cargo.toml"))?
@@ -40,10 +40,16 @@ where
     Ok(())
 }
 
-fn dedup_crates<'a>(crates: impl Iterator<Item = &'a CrateType>) -> Vec<&'a CrateType> {
-    let mut crates: Vec<&CrateType> = crates.collect();
-    crates.sort_by_key(|x| &x.cargo_name);
-    crates.dedup_by_key(|x| &x.cargo_name);
+/// The crate names, along with the package names mapped through the linking configuration.
+fn dedup_crates<'a>(
+    crates: impl Iterator<Item = &'a CrateType>,
+    linking_config: &'a linking::LinkingConfiguration,
+) -> Vec<(&'a str, &'a str)> {
+    let mut crates: Vec<_> = crates
+        .map(|c| (c.name.as_str(), linking_config.cargo_name(c)))
+        .collect();
+    crates.sort_unstable();
+    crates.dedup();
     crates
 }
 
@@ -57,7 +63,12 @@ fn create_file_and_dir<P: AsRef<Path>>(file: P) -> io::Result<fs::File> {
     fs::File::create(file)
 }
 
-fn cargotoml_contents<'a, I: Iterator<Item = &'a CrateType>>(lib_name: &str, crates: I) -> String {
+/// A crate whose package name differs from the crate name is renamed with `package`, as cargo
+/// would otherwise expose `crate-name` as `crate_name`.
+fn cargotoml_contents<'a, I>(lib_name: &str, crates: I) -> String
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
     format!(
         r#"[package]
 name = "{lib_name}"
@@ -75,7 +86,13 @@ kserd = {{ version = "0.3", default-features = false, features = [ "format" ] }}
 "#,
         lib_name = lib_name,
         crates = crates
-            .map(|c| format!(r#"{} = "*""#, c.cargo_name))
+            .map(|(name, package)| {
+                if name == package {
+                    format!(r#"{} = "*""#, name)
+                } else {
+                    format!(r#"{} = {{ package = "{}", version = "*" }}"#, name, package)
+                }
+            })
             .collect::<Vec<_>>()
             .join("\n")
     )
@@ -111,9 +128,18 @@ mod tests {
             CrateType::parse_str("extern crate rand as rnd;").unwrap(),
             CrateType::parse_str("extern crate third;").unwrap(),
         ];
-        let crates = dedup_crates(crates.iter());
-
-        let v: Vec<_> = crates.iter().map(|x| &x.cargo_name).collect();
-        assert_eq!(&v, &["rand", "third"]);
+        let mut linking = linking::LinkingConfiguration::default();
+        let v = dedup_crates(crates.iter(), &linking);
+        assert_eq!(&v, &[("rand", "rand"), ("third", "third")]);
+
+        linking
+            .crate_names
+            .insert("third".to_string(), "third-party".to_string());
+        let v = dedup_crates(crates.iter(), &linking);
+        assert_eq!(&v, &[("rand", "rand"), ("third", "third-party")]);
+
+        let toml = cargotoml_contents("lib", v.into_iter());
+        assert!(toml.contains("rand = \"*\"\n"));
+        assert!(toml.contains("third = { package = \"third-party\", version = \"*\" }"));
     }
 }
@@ -1,12 +1,16 @@
 use ::kserd::Kserd;
 use libloading::{Library, Symbol};
+use std::borrow::Cow;
 use std::path::Path;
 
 /// We don't type anything here. You must be **VERY** careful to pass through the correct borrow to match the
 /// function signature!
-type DataFunc<D> = unsafe fn(D) -> Kserd<'static>;
+type DataFunc<D> = unsafe fn(D) -> (Kserd<'static>, String);
 
-type ExecResult = Result<(Kserd<'static>, Library), &'static str>;
+/// The result data, the type name of the result, and the loaded library.
+///
+/// Fallible statements which return an `Err` are an error, with the error message.
+type ExecResult = Result<(Kserd<'static>, String, Library), Cow<'static, str>>;
 
 pub(crate) fn exec<P: AsRef<Path>, D>(
     library_file: P,
@@ -27,8 +31,12 @@ fn exec_no_redirect<P: AsRef<Path>, Data>(
     let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe { func(app_data) }));
 
     match res {
-        Ok(kserd) => Ok((kserd, lib)),
-        Err(_) => Err("a panic occured with evaluation"),
+        Ok((kserd, type_name)) if type_name == crate::code::ERR_TYPE_NAME => {
+            let msg = kserd.str().unwrap_or_default();
+            Err(Cow::Owned(format!("evaluation returned an error: {}", msg)))
+        }
+        Ok((kserd, type_name)) => Ok((kserd, type_name, lib)),
+        Err(_) => Err(Cow::Borrowed("a panic occured with evaluation")),
     }
 }
 
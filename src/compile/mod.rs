@@ -4,7 +4,7 @@ mod build;
 mod construct;
 mod execute;
 
-pub use self::build::{compile, unshackle_library_file, CompilationError};
+pub use self::build::{compile, expand, unshackle_library_file, CompilationError};
 pub use self::construct::build_compile_dir;
 pub(crate) use self::execute::exec;
 
@@ -38,6 +38,7 @@ mod tests {
         let r = exec::<_, _>(path, "_lib_intern_eval", &()).unwrap(); // execute library fn
 
         assert_eq!(r.0, Kserd::new_num(4));
+        assert_eq!(r.1, "i32");
     }
 
     #[test]
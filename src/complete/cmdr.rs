@@ -10,6 +10,7 @@ use super::*;
 use cmdtree::completion::CompletionInfo;
 use cmdtree::Commander;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 /// Completion items for the [`cmdtree`] class and action structure.
 ///
@@ -132,6 +133,69 @@ impl ActionArgComplete {
     }
 }
 
+/// A function which completes the arguments of an action.
+///
+/// It is given the argument scoped inputs and returns the completions of the final word.
+pub type ArgCompleterFn = dyn Fn(&ArgComplete) -> Vec<String> + Send;
+
+/// Completion of [`cmdtree`] action arguments using registered completer functions.
+///
+/// # Example
+/// ```rust
+/// use papyrus::complete::cmdr::*;
+/// use papyrus::cmdtree::*;
+/// use std::collections::BTreeMap;
+///
+/// let cmdr = Builder::default_config("example-completion")
+///             .begin_class("case", "change case")
+///                 .add_action("upper", "make uppercase", |_, _| ())
+///             .end_class()
+///             .into_commander()
+///             .unwrap();
+///
+/// let mut completers = BTreeMap::new();
+/// let f = |arg: &ArgComplete| vec![format!("{}-completed", arg.word)];
+/// completers.insert("case..upper".to_string(), Box::new(f) as Box<ArgCompleterFn>);
+///
+/// let cmpltr = CustomArgCompleter::build(&cmdr, &completers);
+///
+/// let matches = cmpltr.complete(":case upper some").collect::<Vec<_>>();
+/// assert_eq!(matches, vec!["some-completed".to_string()]);
+/// ```
+///
+/// [`cmdtree`]: cmdtree
+pub struct CustomArgCompleter<'a> {
+    inner: ActionArgComplete,
+    completers: &'a BTreeMap<String, Box<ArgCompleterFn>>,
+}
+
+impl<'a> CustomArgCompleter<'a> {
+    /// Build the `CustomArgCompleter` with completer functions keyed by the qualified path of the
+    /// action.
+    pub fn build<T>(
+        cmdr: &Commander<T>,
+        completers: &'a BTreeMap<String, Box<ArgCompleterFn>>,
+    ) -> Self {
+        let inner = ActionArgComplete::build(cmdr);
+
+        Self { inner, completers }
+    }
+
+    /// Returns the start position of the _last_ word which is broken in context to arguments.
+    pub fn word_break(line: &str) -> usize {
+        word_break_start(line, &[' '])
+    }
+
+    /// Get the completions of an actions arguments if it matches the line.
+    pub fn complete(&self, line: &str) -> impl Iterator<Item = String> {
+        self.completers
+            .iter()
+            .find_map(|(path, f)| self.inner.find(line, &[path.as_str()]).map(|x| f(&x)))
+            .unwrap_or_default()
+            .into_iter()
+    }
+}
+
 /// The argument scoped inputs for completing.
 ///
 /// The information here is scoped to the argument slice of a line, ie
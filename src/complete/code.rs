@@ -112,6 +112,17 @@ impl CodeCompleter {
             .collect()
     }
 
+    /// Find the definition of a path, such as `Vec::retain`, injected into the current repl state.
+    pub fn definition(&self, path: &str, cache: &CodeCache) -> Option<Match> {
+        let session = racer::Session::new(&cache.cache, None);
+
+        let (contents, pos) = self.inject(path);
+
+        session.cache_file_contents(LIBRS, contents);
+
+        racer::find_definition(LIBRS, Location::Point(pos), &session)
+    }
+
     /// Inject code into the current source code and return the amended code,
     /// along with the byte position to complete from.
     fn inject(&self, injection: &str) -> (String, BytePos) {
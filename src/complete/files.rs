@@ -0,0 +1,196 @@
+//! Complete file system paths.
+
+use super::*;
+use crate::code::StaticFiles;
+use cmdr::ActionArgComplete;
+use std::fs;
+use std::path::Path;
+
+type IterRet<'a> = Box<dyn Iterator<Item = String> + 'a>;
+
+const GLOB_CHARS: &[char] = &['*', '?', '['];
+const SLASHES: &[char] = &['/', '\\'];
+
+/// A completer that completes file system paths, such as the `static-files add` action.
+///
+/// The `static-files rm` action completes the paths of the imported static files.
+///
+/// # Example
+/// ```rust
+/// let data = papyrus::repl::ReplData::<()>::default();
+///
+/// let cmpltr = papyrus::complete::files::FilesCompleter::build(&data.cmdtree, data.static_files());
+///
+/// let matches = cmpltr.complete(":static-files add src/lib").collect::<Vec<_>>();
+///
+/// assert_eq!(matches, vec!["src/lib.rs".to_string()]);
+/// ```
+pub struct FilesCompleter {
+    inner: ActionArgComplete,
+    static_files: Vec<String>,
+}
+
+impl FilesCompleter {
+    /// Build the `FilesCompleter`.
+    pub fn build<T>(cmdr: &cmdtree::Commander<T>, static_files: &StaticFiles) -> Self {
+        let static_files = static_files
+            .iter()
+            .map(|x| path_str(&x.path))
+            .collect::<Vec<String>>();
+
+        let inner = ActionArgComplete::build(cmdr);
+
+        Self {
+            inner,
+            static_files,
+        }
+    }
+
+    /// Returns the start position of the _last_ word which is broken in context to file paths.
+    pub fn word_break(line: &str) -> usize {
+        word_break_start(line, &[' '])
+    }
+
+    /// Get the completions of an actions arguments if it matches the line.
+    pub fn complete<'a>(&'a self, line: &'a str) -> IterRet<'a> {
+        let fs_actions = [
+            "static-files..add",
+            "..load",
+            "session..save",
+            "session..load",
+            "extern..add",
+        ];
+        let static_actions = ["static-files..rm"];
+
+        if let Some(x) = self.inner.find(line, &fs_actions) {
+            Box::new(complete_fs_path(x.word).into_iter())
+        } else if let Some(x) = self.inner.find(line, &static_actions) {
+            let word = x.word.replace("\\", "/");
+            Box::new(
+                self.static_files
+                    .iter()
+                    .filter(move |p| p.starts_with(word.as_str()))
+                    .cloned(),
+            )
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+}
+
+/// Return the file system paths that complete `word`. Directories end with a `/`.
+///
+/// A glob pattern completes to the paths it matches, and a directory also completes to the glob
+/// of the rust source files it contains.
+fn complete_fs_path(word: &str) -> Vec<String> {
+    if word.contains(GLOB_CHARS) {
+        return glob::glob(word)
+            .map(|paths| paths.filter_map(Result::ok).map(|p| path_str(&p)).collect())
+            .unwrap_or_default();
+    }
+
+    let (dir, file) = match word.rfind(SLASHES) {
+        Some(idx) => word.split_at(idx + 1),
+        None => ("", word),
+    };
+
+    let dir_path = if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    };
+
+    let mut paths = fs::read_dir(dir_path)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            // hidden files are only completed if asked for
+            if !name.starts_with(file) || (name.starts_with('.') && !file.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let slash = if is_dir { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort();
+
+    if !dir.is_empty() && file.is_empty() && dir_path.is_dir() {
+        paths.insert(0, format!("{}*.rs", dir));
+    }
+
+    paths
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().replace("\\", "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_fs_path_test() {
+        let dir = "target/testing/complete-fs-path/";
+        fs::create_dir_all(format!("{}inner", dir)).unwrap();
+        fs::write(format!("{}one.rs", dir), "").unwrap();
+        fs::write(format!("{}other.txt", dir), "").unwrap();
+        fs::write(format!("{}.hidden", dir), "").unwrap();
+
+        assert_eq!(
+            complete_fs_path(dir),
+            vec![
+                format!("{}*.rs", dir),
+                format!("{}inner/", dir),
+                format!("{}one.rs", dir),
+                format!("{}other.txt", dir),
+            ]
+        );
+
+        assert_eq!(
+            complete_fs_path(&format!("{}o", dir)),
+            vec![format!("{}one.rs", dir), format!("{}other.txt", dir)]
+        );
+
+        assert_eq!(
+            complete_fs_path(&format!("{}.h", dir)),
+            vec![format!("{}.hidden", dir)]
+        );
+
+        assert_eq!(
+            complete_fs_path(&format!("{}*.rs", dir)),
+            vec![format!("{}one.rs", dir)]
+        );
+
+        assert_eq!(
+            complete_fs_path("target/testing/complete-fs-path-none/"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn static_files_test() {
+        let mut data = crate::repl::ReplData::<()>::default();
+        data.with_compilation_dir("target/testing/complete-static-files")
+            .unwrap();
+        data.add_static_file("foo.rs".into(), "pub fn a() {}")
+            .unwrap();
+        data.add_static_file("foo/bar.rs".into(), "pub fn b() {}")
+            .unwrap();
+        data.add_static_file("baz.rs".into(), "pub fn c() {}")
+            .unwrap();
+
+        let cmpltr = FilesCompleter::build(&data.cmdtree, data.static_files());
+
+        assert_eq!(
+            cmpltr.complete(":static-files rm fo").collect::<Vec<_>>(),
+            vec!["foo/bar.rs".to_string(), "foo.rs".to_string()]
+        );
+        assert_eq!(cmpltr.complete(":static-files rm ").count(), 3);
+        assert_eq!(cmpltr.complete(":mod switch fo").count(), 0);
+    }
+}
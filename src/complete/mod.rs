@@ -10,7 +10,9 @@
 pub mod cmdr;
 #[cfg(feature = "racer-completion")]
 pub mod code;
+pub mod files;
 pub mod modules;
+pub mod stmts;
 
 /// Returns the start position of the _last_ word which is broken by any of the characters.
 ///
@@ -56,7 +56,13 @@ impl ModulesCompleter {
 
     /// Get the completions of an actions arguments if it matches the line.
     pub fn complete<'a>(&'a self, line: &'a str) -> IterRet<'a> {
-        let actions = ["mod..switch"];
+        let actions = [
+            "mod..switch",
+            "mod..clear",
+            "mod..use",
+            "mod..rm",
+            "mod..mv",
+        ];
 
         self.inner
             .find(line, &actions)
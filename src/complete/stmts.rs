@@ -0,0 +1,109 @@
+//! Complete statement indices.
+
+use super::*;
+use crate::code::SourceCode;
+use cmdr::ActionArgComplete;
+
+type IterRet<'a> = Box<dyn Iterator<Item = (String, &'a str)> + 'a>;
+
+/// A completer that completes statement indices, such as the `edit stmt alter` action.
+///
+/// Each index is paired with the statement source code, which can be used as a preview.
+///
+/// # Example
+/// ```rust
+/// use papyrus::code::{SourceCode, Statement, StmtGrp};
+///
+/// let mut src = SourceCode::default();
+/// src.stmts.push(StmtGrp(vec![Statement { expr: "let a = 1".to_string(), semi: true }]));
+/// src.stmts.push(StmtGrp(vec![Statement { expr: "a + 1".to_string(), semi: false }]));
+///
+/// let cmdr = papyrus::repl::ReplData::<()>::default().cmdtree;
+///
+/// let cmpltr = papyrus::complete::stmts::StmtsCompleter::build(&cmdr, &src);
+///
+/// let mut matches = cmpltr.complete(":edit stmt alter ");
+///
+/// assert_eq!(matches.next(), Some(("0".to_string(), "let a = 1;")));
+/// assert_eq!(matches.next(), Some(("1".to_string(), "a + 1")));
+/// assert_eq!(matches.next(), None);
+/// ```
+pub struct StmtsCompleter {
+    inner: ActionArgComplete,
+    stmts: Vec<String>,
+}
+
+impl StmtsCompleter {
+    /// Build the `StmtsCompleter` with the statements of the source code.
+    pub fn build<T>(cmdr: &cmdtree::Commander<T>, src: &SourceCode) -> Self {
+        let stmts = src
+            .stmts
+            .iter()
+            .map(|x| x.src_line().trim().to_string())
+            .collect();
+
+        let inner = ActionArgComplete::build(cmdr);
+
+        Self { inner, stmts }
+    }
+
+    /// Returns the start position of the _last_ word which is broken in context to statements.
+    pub fn word_break(line: &str) -> usize {
+        word_break_start(line, &[' '])
+    }
+
+    /// Get the completions of an actions arguments if it matches the line.
+    ///
+    /// The first element is the statement index, the second is the statement source code.
+    /// Only the first argument is completed.
+    pub fn complete<'a>(&'a self, line: &'a str) -> IterRet<'a> {
+        let actions = ["edit.stmt..alter", "edit.stmt..replace"];
+
+        self.inner
+            .find(line, &actions)
+            .filter(|x| x.word_start == 0)
+            .map(|x| {
+                let iter = self
+                    .stmts
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, stmt)| (idx.to_string(), stmt.as_str()))
+                    .filter(move |(idx, _)| idx.starts_with(x.word));
+                Box::new(iter) as IterRet<'a>
+            })
+            .unwrap_or_else(|| Box::new(std::iter::empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::{Statement, StmtGrp};
+
+    #[test]
+    fn complete_test() {
+        let mut src = SourceCode::default();
+        for i in 0..12 {
+            src.stmts.push(StmtGrp(vec![Statement {
+                expr: format!("let a{} = {}", i, i),
+                semi: true,
+            }]));
+        }
+
+        let cmdr = crate::repl::ReplData::<()>::default().cmdtree;
+        let cmpltr = StmtsCompleter::build(&cmdr, &src);
+
+        assert_eq!(
+            cmpltr.complete(":edit stmt replace 1").collect::<Vec<_>>(),
+            vec![
+                ("1".to_string(), "let a1 = 1;"),
+                ("10".to_string(), "let a10 = 10;"),
+                ("11".to_string(), "let a11 = 11;"),
+            ]
+        );
+        assert_eq!(cmpltr.complete(":edit stmt alter ").count(), 12);
+        // only the index argument
+        assert_eq!(cmpltr.complete(":edit stmt replace 1 a").count(), 0);
+        assert_eq!(cmpltr.complete(":mod switch 1").count(), 0);
+    }
+}
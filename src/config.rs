@@ -0,0 +1,377 @@
+//! User configuration.
+//!
+//! The user configuration is a TOML file at `~/.config/papyrus/config.toml`. A project can
+//! override it with a `.papyrus.toml` file in the working directory, settings in the project file
+//! replace the user settings, while lists and persistent module code are appended.
+//!
+//! ```toml
+//! prompt_colour = "bright blue"
+//! out_colour = "green"
+//! loaded_libs_size_limit = 0
+//! compilation_dir = "/tmp/papyrus"
+//! history_size = 500
+//! format_width = 100
+//! auto_add_crates = true
+//! dependencies = ["regex", "proc-macro2"]
+//! static_files = ["helpers.rs", "scratch/*.rs"]
+//! persistent_module_code = "use std::collections::*;"
+//!
+//! [aliases]
+//! alpha = ":mod switch scratch/alpha ;; :static-files ls"
+//! ```
+//!
+//! Colours are the names of [`colored::Color`], such as `"cyan"` or `"bright green"`. The
+//! `[aliases]` table holds the command aliases defined with `:alias`, the key being the alias name
+//! and the value the steps it expands to.
+//!
+//! Use [`ReplData::with_config`](crate::repl::ReplData::with_config) to apply a `Config` to a
+//! REPL.
+//!
+//! # Startup Files
+//! The `papyrus` binary loads the startup files `~/.papyrusrc.rs` and `.papyrusrc.rs` in the
+//! working directory, in that order, before running the REPL. Failures are printed as warnings and
+//! do not stop the REPL from running. Loading is opt-in for other applications, use
+//! [`startup_files`] with
+//! [`ReplData::load_startup_file`](crate::repl::ReplData::load_startup_file).
+use crate::code::AddingStaticFileError;
+use colored::Color;
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
+
+/// The name of the project configuration file, read from the working directory.
+pub const PROJECT_CONFIG: &str = ".papyrus.toml";
+
+/// The name of the startup file, which is evaluated when the REPL is run.
+pub const STARTUP_FILE: &str = ".papyrusrc.rs";
+
+/// The paths to the startup files which exist, the global `$HOME/.papyrusrc.rs` followed by the
+/// `.papyrusrc.rs` in the working directory.
+pub fn startup_files() -> Vec<PathBuf> {
+    let global = dirs::home_dir().unwrap_or_default().join(STARTUP_FILE);
+    let local = std::env::current_dir()
+        .unwrap_or_default()
+        .join(STARTUP_FILE);
+
+    let mut files = vec![global];
+    if !files.contains(&local) {
+        files.push(local);
+    }
+    files.retain(|x| x.is_file());
+    files
+}
+
+/// The path to the user configuration file, `$HOME/.config/papyrus/config.toml`.
+pub fn user_config_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".config/papyrus/config.toml")
+}
+
+/// REPL settings read from configuration files.
+///
+/// Settings which are not specified are `None` or empty, such that the REPL default is kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// The colour of the prompt region.
+    pub prompt_colour: Option<Color>,
+    /// The colour of the out component.
+    pub out_colour: Option<Color>,
+    /// The number of loaded libraries kept in memory.
+    pub loaded_libs_size_limit: Option<usize>,
+    /// The directory for which compilation is done within.
+    pub compilation_dir: Option<PathBuf>,
+    /// The number of inputs kept in the history.
+    pub history_size: Option<usize>,
+    /// The width limit of formatted results.
+    pub format_width: Option<u16>,
+    /// Add crates referenced by `use` and paths as dependencies.
+    pub auto_add_crates: Option<bool>,
+    /// Crates added as dependencies, such as `regex` or `proc-macro2`.
+    pub dependencies: Vec<String>,
+    /// File paths or glob patterns imported as static files.
+    pub static_files: Vec<String>,
+    /// Code appended to the persistent module code.
+    pub persistent_module_code: Option<String>,
+}
+
+impl Config {
+    /// Read the user configuration file, overridden by the project configuration file in the
+    /// working directory.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = Config::read(user_config_path())?;
+        config.merge(Config::read(PROJECT_CONFIG)?);
+        Ok(config)
+    }
+
+    /// Read a configuration file. A file which does not exist has no settings.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let table = read_table(path.as_ref())?;
+
+        Ok(Config {
+            prompt_colour: get_colour(&table, "prompt_colour")?,
+            out_colour: get_colour(&table, "out_colour")?,
+            loaded_libs_size_limit: get_int(&table, "loaded_libs_size_limit")?,
+            compilation_dir: get_str(&table, "compilation_dir")?.map(PathBuf::from),
+            history_size: get_int(&table, "history_size")?,
+            format_width: get_int(&table, "format_width")?,
+            auto_add_crates: get_bool(&table, "auto_add_crates")?,
+            dependencies: get_strs(&table, "dependencies")?,
+            static_files: get_strs(&table, "static_files")?,
+            persistent_module_code: get_str(&table, "persistent_module_code")?.map(String::from),
+        })
+    }
+
+    /// Override the settings with those specified in `other`. Lists and persistent module code
+    /// are appended.
+    pub fn merge(&mut self, other: Config) {
+        let Config {
+            prompt_colour,
+            out_colour,
+            loaded_libs_size_limit,
+            compilation_dir,
+            history_size,
+            format_width,
+            auto_add_crates,
+            dependencies,
+            static_files,
+            persistent_module_code,
+        } = other;
+
+        self.prompt_colour = prompt_colour.or(self.prompt_colour);
+        self.out_colour = out_colour.or(self.out_colour);
+        self.loaded_libs_size_limit = loaded_libs_size_limit.or(self.loaded_libs_size_limit);
+        self.compilation_dir = compilation_dir.or_else(|| self.compilation_dir.take());
+        self.history_size = history_size.or(self.history_size);
+        self.format_width = format_width.or(self.format_width);
+        self.auto_add_crates = auto_add_crates.or(self.auto_add_crates);
+        self.dependencies.extend(dependencies);
+        self.static_files.extend(static_files);
+        self.persistent_module_code =
+            match (self.persistent_module_code.take(), persistent_module_code) {
+                (Some(a), Some(b)) => Some(format!("{}\n{}", a, b)),
+                (a, b) => b.or(a),
+            };
+    }
+}
+
+/// Errors from reading or applying a configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// An io error occurred.
+    Io(io::Error),
+    /// The file is not valid TOML.
+    Parse(toml::de::Error),
+    /// The setting has a value of the wrong type, with a description of the expected value.
+    Invalid(String, &'static str),
+    /// The static file could not be imported.
+    StaticFile(PathBuf, AddingStaticFileError),
+}
+
+impl error::Error for ConfigError {}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "io error: {}", e),
+            ConfigError::Parse(e) => write!(f, "failed to parse config: {}", e),
+            ConfigError::Invalid(key, expected) => write!(f, "`{}` expects {}", key, expected),
+            ConfigError::StaticFile(path, e) => {
+                write!(f, "failed to add static file `{}`: {}", path.display(), e)
+            }
+        }
+    }
+}
+
+/// Read the `[aliases]` table of a configuration file. A file which does not exist has no
+/// aliases.
+pub fn read_aliases<P: AsRef<Path>>(path: P) -> Result<BTreeMap<String, String>, ConfigError> {
+    let table = read_table(path.as_ref())?;
+
+    let aliases = table
+        .get("aliases")
+        .and_then(Value::as_table)
+        .map(|aliases| {
+            aliases
+                .iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(aliases)
+}
+
+/// Write the `[aliases]` table of a configuration file, keeping the other settings in the file.
+/// The file and its parent directories are created if they do not exist.
+pub fn write_aliases<P: AsRef<Path>>(
+    path: P,
+    aliases: &BTreeMap<String, String>,
+) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let mut table = read_table(path)?;
+
+    let aliases = aliases
+        .iter()
+        .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+        .collect();
+    table.insert("aliases".to_string(), Value::Table(aliases));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(ConfigError::Io)?;
+    }
+
+    let s = toml::to_string(&Value::Table(table))
+        .map_err(|e| ConfigError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    fs::write(path, s).map_err(ConfigError::Io)
+}
+
+fn read_table(path: &Path) -> Result<Table, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(s) => toml::from_str::<Table>(&s).map_err(ConfigError::Parse),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Table::new()),
+        Err(e) => Err(ConfigError::Io(e)),
+    }
+}
+
+fn get_str<'a>(table: &'a Table, key: &str) -> Result<Option<&'a str>, ConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(ConfigError::Invalid(key.to_string(), "a string")),
+    }
+}
+
+fn get_int<T: TryFrom<i64>>(table: &Table, key: &str) -> Result<Option<T>, ConfigError> {
+    let invalid = || ConfigError::Invalid(key.to_string(), "a positive integer");
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::Integer(i)) => T::try_from(*i).map(Some).map_err(|_| invalid()),
+        Some(_) => Err(invalid()),
+    }
+}
+
+fn get_bool(table: &Table, key: &str) -> Result<Option<bool>, ConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => Err(ConfigError::Invalid(key.to_string(), "true or false")),
+    }
+}
+
+fn get_strs(table: &Table, key: &str) -> Result<Vec<String>, ConfigError> {
+    let invalid = || ConfigError::Invalid(key.to_string(), "an array of strings");
+    match table.get(key) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(a)) => a
+            .iter()
+            .map(|x| x.as_str().map(String::from).ok_or_else(invalid))
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+fn get_colour(table: &Table, key: &str) -> Result<Option<Color>, ConfigError> {
+    get_str(table, key)?
+        .map(|s| {
+            s.parse()
+                .map_err(|_| ConfigError::Invalid(key.to_string(), "a colour name"))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_round_trip_test() {
+        let dir = Path::new("target/testing/config-aliases");
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "other = 1\n").unwrap();
+
+        let mut aliases = BTreeMap::new();
+        aliases.insert("a".to_string(), ":mod switch a ;; 1 + 1".to_string());
+        write_aliases(&path, &aliases).unwrap();
+
+        assert_eq!(read_aliases(&path).unwrap(), aliases);
+        let table = read_table(&path).unwrap();
+        assert_eq!(table.get("other").and_then(Value::as_integer), Some(1));
+
+        assert!(read_aliases(dir.join("missing.toml")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_and_merge_test() {
+        let dir = Path::new("target/testing/config-read");
+        fs::create_dir_all(dir).unwrap();
+        let user = dir.join("config.toml");
+        let project = dir.join(PROJECT_CONFIG);
+        fs::write(
+            &user,
+            r#"
+prompt_colour = "bright blue"
+history_size = 500
+dependencies = ["regex"]
+persistent_module_code = "use std::fmt;"
+
+[aliases]
+a = "1"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            &project,
+            r#"
+history_size = 20
+format_width = 80
+auto_add_crates = true
+dependencies = ["proc-macro2"]
+persistent_module_code = "use std::io;"
+"#,
+        )
+        .unwrap();
+
+        let mut config = Config::read(&user).unwrap();
+        config.merge(Config::read(&project).unwrap());
+
+        assert_eq!(
+            config,
+            Config {
+                prompt_colour: Some(Color::BrightBlue),
+                history_size: Some(20),
+                format_width: Some(80),
+                auto_add_crates: Some(true),
+                dependencies: vec!["regex".to_string(), "proc-macro2".to_string()],
+                persistent_module_code: Some("use std::fmt;\nuse std::io;".to_string()),
+                ..Config::default()
+            }
+        );
+
+        assert_eq!(
+            Config::read(dir.join("missing.toml")).unwrap(),
+            Config::default()
+        );
+
+        fs::write(&project, "history_size = -1").unwrap();
+        assert!(matches!(
+            Config::read(&project),
+            Err(ConfigError::Invalid(key, _)) if key == "history_size"
+        ));
+        fs::write(&project, "auto_add_crates = \"yes\"").unwrap();
+        assert!(matches!(
+            Config::read(&project),
+            Err(ConfigError::Invalid(key, _)) if key == "auto_add_crates"
+        ));
+        fs::write(&project, "out_colour = \"octarine\"").unwrap();
+        assert!(matches!(
+            Config::read(&project),
+            Err(ConfigError::Invalid(key, _)) if key == "out_colour"
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! Finding crates referenced by paths in the input.
+use super::*;
+use std::collections::{BTreeSet, HashSet};
+use syn::visit::{self, Visit};
+use syn::{Ident, UseTree};
+
+/// Path roots that are not crates, such as keywords and primitive types (`u8::MAX`).
+const NOT_CRATES: &[&str] = &[
+    "crate", "self", "super", "bool", "char", "str", "f32", "f64", "i8", "i16", "i32", "i64",
+    "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// The root names of paths in the input which could refer to crates.
+///
+/// These are the first segment of `use` trees and of paths with more than one segment, such as
+/// `regex` in `use regex::Regex;` or `rand` in `rand::random()`. Roots that are defined or
+/// imported by the input or the given `items`, primitive types, and names which are not snake
+/// case are excluded. Paths within macro invocations are not inspected.
+pub fn crate_roots<'a, I>(input: &'a Input, items: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut roots = Roots::default();
+
+    for cr in &input.crates {
+        roots.defined.insert(cr.name.clone());
+        roots.defined.extend(cr.alias.clone());
+    }
+
+    for item in input.items.iter().map(|x| x.0.as_str()).chain(items) {
+        if let Ok(file) = syn::parse_str::<syn::File>(item) {
+            roots.visit_file(&file);
+        }
+    }
+
+    if !input.stmts.is_empty() {
+        let code = input
+            .stmts
+            .iter()
+            .map(|x| x.expr.as_str())
+            .collect::<Vec<_>>()
+            .join(";\n");
+        if let Ok(block) = syn::parse_str::<syn::Block>(&format!("{{ {} }}", code)) {
+            roots.visit_block(&block);
+        }
+    }
+
+    let Roots { roots, defined } = roots;
+
+    roots
+        .into_iter()
+        .filter(|x| !defined.contains(x))
+        .filter(|x| !NOT_CRATES.contains(&x.as_str()))
+        .filter(|x| x.starts_with(|c: char| c.is_ascii_lowercase()))
+        .collect()
+}
+
+#[derive(Default)]
+struct Roots {
+    roots: BTreeSet<String>,
+    defined: HashSet<String>,
+}
+
+impl Roots {
+    fn define(&mut self, ident: &Ident) {
+        self.defined.insert(ident.to_string());
+    }
+
+    /// `depth` is the number of path segments before the tree.
+    fn use_tree(&mut self, tree: &UseTree, parent: Option<&Ident>, depth: usize) {
+        let root = depth == 0;
+        match tree {
+            UseTree::Path(p) => {
+                if root {
+                    self.roots.insert(p.ident.to_string());
+                }
+                self.use_tree(&p.tree, Some(&p.ident), depth + 1);
+            }
+            UseTree::Name(n) => {
+                if root {
+                    self.roots.insert(n.ident.to_string());
+                }
+                match parent {
+                    // `use regex::{self};` imports the crate itself
+                    Some(_) if n.ident == "self" && depth == 1 => (),
+                    Some(parent) if n.ident == "self" => self.define(parent),
+                    Some(_) => self.define(&n.ident),
+                    None => (), // `use regex;` imports the crate itself
+                }
+            }
+            UseTree::Rename(r) => {
+                if root {
+                    self.roots.insert(r.ident.to_string());
+                }
+                self.define(&r.rename);
+            }
+            UseTree::Glob(_) => (),
+            UseTree::Group(g) => {
+                for tree in &g.items {
+                    self.use_tree(tree, parent, depth);
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Roots {
+    fn visit_item_use(&mut self, i: &'ast syn::ItemUse) {
+        self.use_tree(&i.tree, None, 0);
+    }
+
+    fn visit_path(&mut self, p: &'ast syn::Path) {
+        if p.segments.len() > 1 || p.leading_colon.is_some() {
+            if let Some(first) = p.segments.first() {
+                self.roots.insert(first.ident.to_string());
+            }
+        }
+        visit::visit_path(self, p);
+    }
+
+    fn visit_item_extern_crate(&mut self, i: &'ast syn::ItemExternCrate) {
+        self.define(&i.ident);
+        if let Some((_, rename)) = &i.rename {
+            self.define(rename);
+        }
+    }
+
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.define(&i.ident);
+        visit::visit_item_mod(self, i);
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.define(&i.ident);
+        visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.define(&i.ident);
+        visit::visit_item_enum(self, i);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        self.define(&i.ident);
+        visit::visit_item_union(self, i);
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        self.define(&i.ident);
+        visit::visit_item_trait(self, i);
+    }
+
+    fn visit_item_type(&mut self, i: &'ast syn::ItemType) {
+        self.define(&i.ident);
+        visit::visit_item_type(self, i);
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.define(&i.sig.ident);
+        visit::visit_item_fn(self, i);
+    }
+
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        self.define(&i.ident);
+        visit::visit_item_const(self, i);
+    }
+
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        self.define(&i.ident);
+        visit::visit_item_static(self, i);
+    }
+
+    fn visit_item_macro(&mut self, i: &'ast syn::ItemMacro) {
+        if let Some(ident) = &i.ident {
+            self.define(ident);
+        }
+        visit::visit_item_macro(self, i);
+    }
+}
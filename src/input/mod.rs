@@ -1,12 +1,17 @@
 use crate::code::{CrateType, Input};
 use syn::Expr;
 
+mod deps;
 mod parse;
+mod scan;
 #[cfg(test)]
 mod tests;
 
+pub use self::deps::crate_roots;
 pub use self::parse::parse_command;
+pub use self::parse::parse_file;
 pub use self::parse::parse_program;
+pub use self::scan::{needs_more, strip_continuations};
 
 /// Possible results from reading input from `InputReader`
 #[derive(Debug, PartialEq)]
@@ -17,7 +22,7 @@ pub enum InputResult {
     Program(Input),
     /// An empty line
     Empty,
-    /// Needs more input; i.e. there is an unclosed delimiter.
+    /// Needs more input; i.e. there is an unclosed delimiter. See [`needs_more`].
     More,
     /// End of file reached.
     Eof,
@@ -33,10 +38,12 @@ pub fn determine_result(input: &str, line: &str, treat_as_cmd: bool) -> InputRes
 
     if treat_as_cmd || is_command(line) {
         parse_command(line)
+    } else if needs_more(input) {
+        InputResult::More
     } else {
-        // check if the final statement ends with a semi
+        // a trailing let binding has nothing to evaluate, so keep reading
         match parse_program(input) {
-            InputResult::Program(input) if input.stmts.last().map(|x| x.semi).unwrap_or(false) => {
+            InputResult::Program(input) if input.stmts.last().map(is_let).unwrap_or(false) => {
                 InputResult::More
             }
             x => x,
@@ -47,3 +54,7 @@ pub fn determine_result(input: &str, line: &str, treat_as_cmd: bool) -> InputRes
 fn is_command(line: &str) -> bool {
     line.starts_with(crate::CMD_PREFIX)
 }
+
+fn is_let(stmt: &crate::code::Statement) -> bool {
+    stmt.semi && stmt.expr.starts_with("let ")
+}
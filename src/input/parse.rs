@@ -1,6 +1,7 @@
 use super::*;
 use crate::code::{self, Statement};
 use quote::ToTokens;
+use std::borrow::Cow;
 use syn::{self, Block, File, Item, Stmt};
 
 /// Parses a line of input as a command.
@@ -17,15 +18,20 @@ pub fn parse_command(line: &str) -> InputResult {
 }
 
 /// Parses a line of input as a program.
+///
+/// Line continuation markers are removed before parsing. If parsing fails and the input is
+/// incomplete (see [`needs_more`](super::needs_more)) then `InputResult::More` is returned.
 pub fn parse_program(code: &str) -> InputResult {
     debug!("parse program: {}", code);
 
+    let more = super::needs_more(code);
+    let code = &*super::strip_continuations(code);
+
     let reterr = |e: syn::Error| {
-        let e = e.to_string();
-        if (&e == "LexError") || (&e == "lex error") {
+        if more {
             InputResult::More
         } else {
-            InputResult::InputError(e)
+            InputResult::InputError(e.to_string())
         }
     };
 
@@ -105,6 +111,110 @@ pub fn parse_program(code: &str) -> InputResult {
         .unwrap_or_else(reterr)
 }
 
+/// Parses a whole file of code, splitting it into inputs that can be evaluated in turn.
+///
+/// The crates and items are placed in the first input, along with any inner attributes such as
+/// `#![allow(unused)]` which lead the file. The statements are grouped, with a group being closed
+/// off at each expression that has no trailing semi-colon. Trailing statements which do not end in
+/// an expression are closed off with a unit `()` expression.
+pub fn parse_file(code: &str) -> Result<Vec<Input>, String> {
+    let (attrs, code) = split_inner_attrs(code);
+
+    // a trailing new line stops a final line comment from swallowing the wrapping block
+    let code = format!("{}\n", code);
+
+    let mut input = match parse_program(&code) {
+        InputResult::Program(input) => input,
+        InputResult::More => {
+            return Err("unexpected end of file, is there an unclosed delimiter?".into())
+        }
+        InputResult::InputError(e) => return Err(e),
+        _ if attrs.is_empty() => return Ok(Vec::new()),
+        _ => Input {
+            items: Vec::new(),
+            stmts: Vec::new(),
+            crates: Vec::new(),
+        },
+    };
+
+    input.items.splice(0..0, attrs);
+
+    Ok(split_stmt_groups(input))
+}
+
+/// Splits the inner attributes which lead the file from the rest of the code.
+///
+/// The code is returned untouched if there are no inner attributes, or if it does not parse.
+fn split_inner_attrs(code: &str) -> (Vec<code::Item>, Cow<'_, str>) {
+    use syn::{
+        buffer::Cursor,
+        parse::{ParseStream, Parser},
+        Attribute,
+    };
+
+    let untouched = || (Vec::new(), Cow::Borrowed(code));
+
+    if !code.contains("#![") {
+        return untouched();
+    }
+
+    let parser = |input: ParseStream| {
+        let attrs = input.call(Attribute::parse_inner)?;
+        let rest = input.step(|cursor| Ok((cursor.token_stream().to_string(), Cursor::empty())))?;
+        Ok((attrs, rest))
+    };
+
+    let (attrs, rest) = match parser.parse_str(code) {
+        Ok(x) if !x.0.is_empty() => x,
+        _ => return untouched(),
+    };
+
+    let attrs = attrs
+        .into_iter()
+        .map(|attr| (fmt(attr.into_token_stream().to_string()), true))
+        .collect();
+
+    (attrs, Cow::Owned(rest))
+}
+
+fn split_stmt_groups(input: Input) -> Vec<Input> {
+    let Input {
+        items,
+        stmts,
+        crates,
+    } = input;
+
+    let mut inputs = vec![Input {
+        items,
+        stmts: Vec::new(),
+        crates,
+    }];
+
+    for stmt in stmts {
+        let close = !stmt.semi;
+        inputs.last_mut().expect("always one").stmts.push(stmt);
+        if close {
+            inputs.push(Input {
+                items: Vec::new(),
+                stmts: Vec::new(),
+                crates: Vec::new(),
+            });
+        }
+    }
+
+    let last = inputs.last_mut().expect("always one");
+    if !last.stmts.is_empty() {
+        last.stmts.push(Statement {
+            expr: "()".to_string(),
+            semi: false,
+        });
+    }
+
+    inputs.retain(|x| !(x.items.is_empty() && x.stmts.is_empty() && x.crates.is_empty()));
+
+    inputs
+}
+
 #[cfg(feature = "format")]
 fn fmt(s: String) -> String {
     crate::fmt::format(&s).unwrap_or(s)
@@ -287,10 +397,6 @@ fn parse_expr(expr: Expr) -> Result<String, String> {
 			error!("haven't handled expr variant Group");
 			Err("haven't handled expr variant Group. Raise a request here https://github.com/kurtlawrence/papyrus/issues".to_string())
 		}
-		Expr::Try(_) => {
-			error!("haven't handled expr variant Try");
-			Err("haven't handled expr variant Try. Raise a request here https://github.com/kurtlawrence/papyrus/issues".to_string())
-		}
 		Expr::Async(_) => {
 			error!("haven't handled expr variant Async");
 			Err("haven't handled expr variant Async. Raise a request here https://github.com/kurtlawrence/papyrus/issues".to_string())
@@ -0,0 +1,330 @@
+//! Scanning of input to determine if it is complete or if more input is required.
+use std::borrow::Cow;
+
+/// Punctuation, longest first such that the longest match is taken.
+const PUNCT: &[&str] = &[
+    "<<=", ">>=", "...", "..=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..", "+", "-", "*", "/", "%", "^", "!", "&",
+    "|", "=", "<", ">", ",", ".", ":", ";", "@", "#", "?", "$",
+];
+
+/// Punctuation that cannot end an input, something must follow it.
+const TRAILING_PUNCT: &[&str] = &[
+    "<<=", ">>=", "..=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=",
+    "/=", "%=", "^=", "&=", "|=", "<<", "+", "-", "*", "/", "%", "^", "!", "&", "|", "=", "<", ",",
+    ".", ":", "@", "#",
+];
+
+/// Keywords that cannot end an input, something must follow it.
+const TRAILING_KEYWORDS: &[&str] = &[
+    "as", "const", "dyn", "else", "enum", "extern", "fn", "for", "if", "impl", "in", "let", "loop",
+    "match", "mod", "move", "mut", "pub", "ref", "static", "struct", "trait", "type", "unsafe",
+    "use", "where", "while",
+];
+
+/// The line continuation marker.
+const CONTINUATION: u8 = b'\\';
+
+#[derive(Debug, PartialEq)]
+enum Last {
+    None,
+    Token,
+    Trailing,
+    Continuation,
+}
+
+#[derive(Debug)]
+struct Scan {
+    /// An unclosed delimiter, string literal, or block comment.
+    open: bool,
+    /// The last token requires something to follow it.
+    last: Last,
+    /// The byte positions of line continuation markers.
+    continuations: Vec<usize>,
+}
+
+/// Determine if the input is incomplete and more input is required.
+///
+/// Input is incomplete if it has unclosed delimiters, string literals, or block comments, if it
+/// ends with an operator or keyword that needs something to follow it (such as `+`, `=>`, or
+/// `else`), or if it ends with the line continuation marker `\`.
+pub fn needs_more(code: &str) -> bool {
+    let scan = scan(code);
+    scan.open || scan.last == Last::Trailing || scan.last == Last::Continuation
+}
+
+/// Remove the line continuation markers (`\` at the end of a line) from the input.
+pub fn strip_continuations(code: &str) -> Cow<'_, str> {
+    let scan = scan(code);
+
+    if scan.continuations.is_empty() {
+        Cow::Borrowed(code)
+    } else {
+        let mut s = String::with_capacity(code.len());
+        let mut from = 0;
+        for pos in scan.continuations {
+            s.push_str(&code[from..pos]);
+            from = pos + 1;
+        }
+        s.push_str(&code[from..]);
+        Cow::Owned(s)
+    }
+}
+
+fn scan(code: &str) -> Scan {
+    let bytes = code.as_bytes();
+    let len = bytes.len();
+
+    let mut scan = Scan {
+        open: false,
+        last: Last::None,
+        continuations: Vec::new(),
+    };
+    let mut delims = Vec::new();
+    let mut mismatched = false;
+
+    let mut i = 0;
+    while i < len {
+        let b = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        match b {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'/' if next == Some(b'/') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map(|p| i + p)
+                    .unwrap_or(len);
+                continue; // comments do not alter the last token
+            }
+            b'/' if next == Some(b'*') => match skip_block_comment(bytes, i + 2) {
+                Some(end) => {
+                    i = end;
+                    continue;
+                }
+                None => return open(scan),
+            },
+            b'"' => match skip_str(bytes, i + 1) {
+                Some(end) => i = end,
+                None => return open(scan),
+            },
+            b'\'' => match skip_char_or_lifetime(code, i) {
+                Some(end) => i = end,
+                None => return open(scan),
+            },
+            CONTINUATION if rest_of_line_is_blank(bytes, i + 1) => {
+                scan.continuations.push(i);
+                scan.last = Last::Continuation;
+                i += 1;
+                continue;
+            }
+            b'(' | b'[' | b'{' => {
+                delims.push(b);
+                i += 1;
+            }
+            b')' | b']' | b'}' => {
+                let expected = match b {
+                    b')' => b'(',
+                    b']' => b'[',
+                    _ => b'{',
+                };
+                if delims.pop() != Some(expected) {
+                    mismatched = true;
+                }
+                i += 1;
+            }
+            b'0'..=b'9' => i = skip_number(bytes, i),
+            b if is_ident_byte(b) => {
+                let end = skip_ident(bytes, i);
+                let word = &code[i..end];
+                match literal_after_prefix(code, word, end) {
+                    Some(Some(lit_end)) => i = lit_end,
+                    Some(None) => return open(scan),
+                    None => {
+                        i = end;
+                        scan.last = if TRAILING_KEYWORDS.contains(&word) {
+                            Last::Trailing
+                        } else {
+                            Last::Token
+                        };
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                let rest = &code[i..];
+                match PUNCT.iter().find(|p| rest.starts_with(*p)) {
+                    Some(p) => {
+                        i += p.len();
+                        scan.last = if TRAILING_PUNCT.contains(p) {
+                            Last::Trailing
+                        } else {
+                            Last::Token
+                        };
+                    }
+                    None => {
+                        // unknown character, let the parser report it
+                        i += rest.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                        scan.last = Last::Token;
+                    }
+                }
+                continue;
+            }
+        }
+
+        scan.last = Last::Token;
+    }
+
+    // mismatched delimiters are an error, which the parser will report
+    scan.open = !mismatched && !delims.is_empty();
+
+    scan
+}
+
+fn open(mut scan: Scan) -> Scan {
+    scan.open = true;
+    scan
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+fn skip_ident(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| !is_ident_byte(b))
+        .map(|p| start + p)
+        .unwrap_or(bytes.len())
+}
+
+fn skip_number(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b if is_ident_byte(b) => i += 1,
+            // a decimal point, but not a range `1..2` or a method `1.max(2)`
+            b'.' if bytes.get(i + 1).map(u8::is_ascii_digit).unwrap_or(false) => i += 1,
+            _ => break,
+        }
+    }
+    i
+}
+
+fn rest_of_line_is_blank(bytes: &[u8], start: usize) -> bool {
+    bytes[start..]
+        .iter()
+        .take_while(|&&b| b != b'\n')
+        .all(u8::is_ascii_whitespace)
+}
+
+/// Returns the end of the nested block comment, `start` is after the opening `/*`.
+fn skip_block_comment(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 1;
+    let mut i = start;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'/', Some(b'*')) => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', Some(b'/')) => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the end of the string literal, `start` is after the opening quote.
+fn skip_str(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the end of the raw string literal, `start` is after the opening quote.
+fn skip_raw_str(bytes: &[u8], start: usize, hashes: usize) -> Option<usize> {
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i] == b'"'
+            && bytes[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&b| b == b'#')
+                .count()
+                == hashes
+        {
+            return Some(i + 1 + hashes);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the end of the character literal or lifetime, `start` is at the quote.
+/// Returns `None` if the character literal is not closed.
+fn skip_char_or_lifetime(code: &str, start: usize) -> Option<usize> {
+    let bytes = code.as_bytes();
+    let i = start + 1;
+
+    match bytes.get(i) {
+        None => None,
+        Some(b'\\') => {
+            // escaped character, skip the escaped char then find the closing quote
+            let close = bytes.get(i + 2..)?.iter().position(|&b| b == b'\'')?;
+            Some(i + 2 + close + 1)
+        }
+        Some(_) => {
+            let c = code[i..].chars().next()?;
+            let after = i + c.len_utf8();
+            if bytes.get(after) == Some(&b'\'') {
+                Some(after + 1) // character literal
+            } else {
+                Some(skip_ident(bytes, i)) // lifetime or label
+            }
+        }
+    }
+}
+
+/// Handles literals that are prefixed, such as byte strings `b"..."`, raw strings `r#"..."#`,
+/// and byte characters `b'a'`.
+///
+/// Returns `None` if the word is not a literal prefix, `Some(None)` if the literal is not closed,
+/// and `Some(Some(end))` otherwise.
+fn literal_after_prefix(code: &str, word: &str, end: usize) -> Option<Option<usize>> {
+    let bytes = code.as_bytes();
+    let next = bytes.get(end).copied();
+
+    match (word, next) {
+        ("b", Some(b'\'')) => Some(skip_char_or_lifetime(code, end)),
+        ("b", Some(b'"')) => Some(skip_str(bytes, end + 1)),
+        ("r", Some(b'"')) | ("br", Some(b'"')) => Some(skip_raw_str(bytes, end + 1, 0)),
+        ("r", Some(b'#')) | ("br", Some(b'#')) => {
+            let hashes = bytes[end..].iter().take_while(|&&b| b == b'#').count();
+            if bytes.get(end + hashes) == Some(&b'"') {
+                Some(skip_raw_str(bytes, end + hashes + 1, hashes))
+            } else if word == "r" {
+                Some(Some(skip_ident(bytes, end + 1))) // raw identifier
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
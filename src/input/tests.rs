@@ -12,6 +12,62 @@ fn test_unclosed_delimiter() {
     assert_eq!(parse_program("let a = \""), InputResult::More);
 }
 
+#[test]
+fn test_needs_more() {
+    // delimiters
+    assert!(needs_more("match x { 1 => "));
+    assert!(needs_more("let f = |x| {"));
+    assert!(!needs_more("let a = (1, [2], {3});"));
+    assert!(!needs_more("(]")); // mismatched is an error, not more
+
+    // strings and characters
+    assert!(needs_more("let s = \"{"));
+    assert!(!needs_more("let s = \"{\";"));
+    assert!(!needs_more("let s = \"\\\"(\";"));
+    assert!(needs_more("let s = r#\"\"{"));
+    assert!(!needs_more("let s = r#\"\"{\"#;"));
+    assert!(!needs_more("let s = b\"(\";"));
+    assert!(!needs_more("let c = '{';"));
+    assert!(!needs_more("let c = '\\'';"));
+    assert!(!needs_more("let c = b'(';"));
+    assert!(!needs_more("fn a<'a>(s: &'a str) -> &'a str { s }"));
+    assert!(!needs_more("let r#match = 1;"));
+
+    // comments
+    assert!(!needs_more("let a = 1; // {"));
+    assert!(needs_more("let a = 1; /* /* */ {"));
+    assert!(!needs_more("let a = 1; /* /* { */ */"));
+
+    // trailing operators and keywords
+    assert!(needs_more("1 +"));
+    assert!(needs_more("let a ="));
+    assert!(needs_more("a &&"));
+    assert!(needs_more("a."));
+    assert!(needs_more("let f = |x|"));
+    assert!(needs_more("if a { 1 } else"));
+    assert!(!needs_more("0.."));
+    assert!(!needs_more("a?"));
+    assert!(!needs_more("std::mem::size_of::<Vec<u8>>"));
+    assert!(!needs_more("1.0"));
+
+    // continuation
+    assert!(needs_more("println!(\"a\"); \\"));
+    assert!(needs_more("println!(\"a\"); \\ \n"));
+    assert!(!needs_more("println!(\"a\"); \\\nprintln!(\"b\");"));
+    assert!(!needs_more("let s = \"a\\\nb\";")); // escaped new line in string
+}
+
+#[test]
+fn test_strip_continuations() {
+    assert_eq!(strip_continuations("1 +\n2"), "1 +\n2");
+    assert_eq!(strip_continuations("a(); \\\nb()"), "a(); \nb()");
+    assert_eq!(strip_continuations("\"a\\\nb\"; \\\nc"), "\"a\\\nb\"; \nc");
+    assert_eq!(
+        parse_program("let a = 1; \\\na"),
+        parse_program("let a = 1;\na")
+    );
+}
+
 #[cfg(feature = "format")] // have to turn formatting on to check this
 #[test]
 fn test_items() {
@@ -270,6 +326,22 @@ fn test_determine_result() {
         InputResult::More
     );
     assert_eq!(determine_result("{", "{", false), InputResult::More);
+    assert_eq!(determine_result("1 +", "1 +", false), InputResult::More);
+    assert_eq!(
+        determine_result("a(); \\", "a(); \\", false),
+        InputResult::More
+    );
+    assert_eq!(
+        determine_result("a();", "a();", false),
+        InputResult::Program(Input {
+            items: Vec::new(),
+            stmts: vec![Statement {
+                expr: "a()".to_string(),
+                semi: true,
+            }],
+            crates: Vec::new()
+        })
+    );
 }
 
 #[test]
@@ -283,3 +355,83 @@ fn fail_parse_program() {
         InputResult::InputError("expected `;`".to_string())
     );
 }
+
+#[test]
+fn test_parse_file() {
+    let inputs = parse_file(
+        "extern crate rand;\nfn a() {}\nlet a = 1;\nfor _ in 0..a {}\nlet b = 2; // trailing",
+    )
+    .unwrap();
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(inputs[0].crates.len(), 1);
+    assert_eq!(inputs[0].items.len(), 1);
+    assert_eq!(inputs[0].stmts.len(), 2);
+    assert_eq!(inputs[1].items.len(), 0);
+    assert_eq!(inputs[1].stmts.len(), 2);
+    assert_eq!(
+        inputs[1].stmts[1],
+        Statement {
+            expr: "()".to_string(),
+            semi: false
+        }
+    );
+
+    let inputs = parse_file("for _ in 0..1 {}\nb").unwrap();
+    assert_eq!(inputs.len(), 2);
+    assert!(inputs.iter().all(|x| x.stmts.len() == 1));
+
+    assert_eq!(parse_file("").unwrap(), vec![]);
+    assert!(parse_file("fn a() {").is_err());
+}
+
+#[test]
+fn test_parse_file_inner_attrs() {
+    let inputs = parse_file(
+        "// leading comment\n#![allow(unused)]\n#![feature(test)]\nfn a() {}\nlet b = 1;\nb",
+    )
+    .unwrap();
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(
+        inputs[0].items,
+        vec![
+            ("#![allow(unused)]".to_string(), true),
+            ("#![feature(test)]".to_string(), true),
+            ("fn a() {}".to_string(), false),
+        ]
+    );
+    assert_eq!(inputs[0].stmts.len(), 2);
+
+    let inputs = parse_file("#![allow(unused)]").unwrap();
+    assert_eq!(
+        inputs[0].items,
+        vec![("#![allow(unused)]".to_string(), true)]
+    );
+}
+
+#[test]
+fn test_crate_roots() {
+    let roots = |code: &str, items: &[&str]| match parse_program(code) {
+        InputResult::Program(input) => crate_roots(&input, items.iter().copied()),
+        x => panic!("expected program: {:?}", x),
+    };
+
+    assert_eq!(roots("use regex::Regex;", &[]), vec!["regex"]);
+    assert_eq!(roots("use regex;", &[]), vec!["regex"]);
+    assert_eq!(roots("use ::rand::{self, Rng};", &[]), vec!["rand"]);
+    assert_eq!(
+        roots(
+            "let a = rand::random::<u8>(); serde_json::to_string(&a)",
+            &[]
+        ),
+        vec!["rand", "serde_json"]
+    );
+
+    // defined or imported names are not crates
+    assert!(roots("use std::io; io::stdin()", &[]) == vec!["std"]);
+    assert!(roots("foo::a()", &["mod foo { pub fn a() {} }"]).is_empty());
+    assert!(roots("use crate::foo::*; self::a(); super::b()", &[]).is_empty());
+    assert!(roots("extern crate rand as rnd; rnd::random::<u8>()", &[]).is_empty());
+
+    // types and primitives
+    assert!(roots("String::new(); u8::MAX; Vec::<i32>::new()", &[]).is_empty());
+}
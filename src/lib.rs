@@ -12,7 +12,7 @@
 //!
 //! ```sh
 //! papyrus=> 2+2
-//! papyrus [out0]: 4
+//! papyrus [out0]: i32 = 4
 //! ```
 //!
 //! Papyrus is in active development, see [changelog](https://github.com/kurtlawrence/papyrus) for updates.
@@ -20,17 +20,29 @@
 //! ## Overview
 //! Papyrus creates a Rust REPL in your terminal. Code can be typed in, line by line with feedback on
 //! the evaluation, or code can be injected via stdin handles.
-//! Each code snippet is evaluated on an expression based system, so terminating with a semi-colon
-//! requires more input.
+//! Each code snippet is evaluated on an expression based system, so terminating with a `let`
+//! binding requires more input. Input also continues on the next line if it has unclosed delimiters,
+//! ends with an operator, or the line ends with `\`.
+//! With the `auto_add_crates` setting enabled, crates referenced by a `use` or a path, such as
+//! `use regex::Regex;`, are added as dependencies without needing `extern crate regex;`.
+//! Snippets can use `.await`, which runs on a small bundled executor or a configured runtime
+//! such as `tokio`.
+//! The `?` operator can be used at the top level, an `Err` is printed as an error and the input is
+//! not kept.
+//! The `papyrus` binary reads settings such as the prompt colours, compilation directory, and default
+//! dependencies from `~/.config/papyrus/config.toml`, a `.papyrus.toml` in the working directory
+//! overrides them.
+//! On startup `~/.papyrusrc.rs` and a `.papyrusrc.rs` in the working directory are loaded, their `use`
+//! imports are available in every module.
 //!
 //! ### Example
 //! ```sh
 //! [lib] papyrus=> 2+2
-//! papyrus [out0]: 4
-//! [lib] papyrus=> println!("Hello, world!");
-//! [lib] papyrus.> out0 * out0
+//! papyrus [out0]: i32 = 4
+//! [lib] papyrus=> let x = out0 * out0;
+//! [lib] papyrus.> println!("Hello, world!"); x
 //! Hello, world!
-//! papyrus [out1]: 16
+//! papyrus [out1]: i32 = 16
 //! [lib] papyrus=> :help
 //! help -- prints the help messages
 //! cancel | c -- returns to the root class
@@ -88,8 +100,17 @@ extern crate crossterm;
 /// Build a repl instance with the default terminal.
 /// If a type is specfied (ie `repl!(String)`) then the repl will be bounded to use
 /// that data type. Otherwise the default `()` will be used.
+/// A second type (ie `repl!(String, MySignal)`) sets the payload type of custom signals.
 #[macro_export]
 macro_rules! repl {
+    // Default Term, with type and custom signal type
+    ($type:ty, $signal:ty) => {{
+        use papyrus;
+        let mut r: papyrus::repl::Repl<_, $type, $signal> = papyrus::repl::Repl::default();
+        r.data = unsafe { r.data.set_data_type(&format!("{}", stringify!($type))) };
+        r
+    }};
+
     // Default Term, with type
     ($type:ty) => {{
         use papyrus;
@@ -135,6 +156,7 @@ pub mod cmds;
 pub mod code;
 pub mod compile;
 pub mod complete;
+pub mod config;
 /// Format rust code snippets using `rustfmt`.
 ///
 /// Requires the _format_ feature.
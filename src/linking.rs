@@ -89,7 +89,7 @@
 //!
 //! ```sh
 //! papyrus=> some_lib::MyStruct::new(20, 30).add_contents()
-//! papyrus [out0]: 50
+//! papyrus [out0]: i32 = 50
 //! ```
 //!
 //! ## Behind the scenes
@@ -138,7 +138,7 @@
 //!
 //! ```sh
 //! papyrus=> app_data.add_contents()
-//! papyrus [out0]: 50
+//! papyrus [out0]: i32 = 50
 //! ```
 //!
 //! ## Notes
@@ -194,7 +194,8 @@
 //! implement `::kserd::ToKserd` which would break! At least at this point it is easy to back out
 //! changes in the temporary REPL session.
 
-use std::collections::HashSet;
+use crate::code::CrateType;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
@@ -229,6 +230,20 @@ pub struct LinkingConfiguration {
     /// It is sometimes necessary to have injected code, especially to solve dependency duplication
     /// issues. See [`the _linking_ module for a description`](crate::linking).
     pub persistent_module_code: String,
+
+    /// Mapping of crate names, as used in Rust, to the package name to use in cargo.
+    ///
+    /// Crates use their Rust name as the package name by default. Some packages have a name
+    /// which differs, such as `proc_macro2` which is the `proc-macro2` package. Mapped crates are
+    /// renamed in the manifest, for example `proc_macro2 = { package = "proc-macro2", ... }`.
+    pub crate_names: HashMap<String, String>,
+
+    /// The function used to run REPL code which uses `.await` to completion.
+    ///
+    /// The function is called with the async block, so it must accept a future and return its
+    /// output, for example `tokio::runtime::Runtime::new().unwrap().block_on`. If `None` a small,
+    /// single-threaded, bundled executor is used.
+    pub async_runtime: Option<String>,
 }
 
 impl Default for LinkingConfiguration {
@@ -238,6 +253,8 @@ impl Default for LinkingConfiguration {
             mutable: false,
             external_libs: HashSet::new(),
             persistent_module_code: String::new(),
+            crate_names: HashMap::new(),
+            async_runtime: None,
         }
     }
 }
@@ -252,6 +269,16 @@ impl LinkingConfiguration {
         self
     }
 
+    /// The package name to use in cargo for the crate, using the [`crate_names`] mapping.
+    ///
+    /// [`crate_names`]: LinkingConfiguration::crate_names
+    pub fn cargo_name<'a>(&'a self, cr: &'a CrateType) -> &'a str {
+        self.crate_names
+            .get(&cr.name)
+            .map(String::as_str)
+            .unwrap_or(&cr.cargo_name)
+    }
+
     /// Constructs the function arguments signature.
     /// Appends result to buffer.
     pub fn construct_fn_args(&self, buf: &mut String) {
@@ -285,7 +312,7 @@ impl LinkingConfiguration {
 pub struct Extern {
     /// Path to rlib.
     path: PathBuf,
-    alias: Option<&'static str>,
+    alias: Option<String>,
 }
 
 impl Extern {
@@ -307,8 +334,8 @@ impl Extern {
     /// same folder that the library exists, there _must_ be a `deps` folder,
     /// even if there is no dependencies. This gets validated as well. The
     /// file must exist on disk.
-    pub fn with_alias<P: AsRef<Path>>(rlib_path: P, alias: &'static str) -> io::Result<Self> {
-        Self::ctor(rlib_path, Some(alias))
+    pub fn with_alias<P: AsRef<Path>>(rlib_path: P, alias: &str) -> io::Result<Self> {
+        Self::ctor(rlib_path, Some(alias.to_string()))
     }
 
     /// Uses the executable name to derive the library name, and
@@ -339,7 +366,7 @@ impl Extern {
         Self::new(path)
     }
 
-    fn ctor<P: AsRef<Path>>(rlib_path: P, alias: Option<&'static str>) -> io::Result<Self> {
+    fn ctor<P: AsRef<Path>>(rlib_path: P, alias: Option<String>) -> io::Result<Self> {
         let path = rlib_path.as_ref();
 
         let path = path.canonicalize()?;
@@ -393,8 +420,8 @@ impl Extern {
     }
 
     /// The alias, is there is one.
-    pub fn alias(&self) -> Option<&'static str> {
-        self.alias
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
     }
 
     /// The canoncialized library path (in `lib*.rlib` format).
@@ -411,7 +438,7 @@ impl Extern {
     pub fn construct_code_str(&self, buf: &mut String) {
         buf.push_str("extern crate "); // 13
         buf.push_str(self.lib_name());
-        if let Some(alias) = self.alias {
+        if let Some(alias) = &self.alias {
             buf.push_str(" as ");
             buf.push_str(alias);
         }
@@ -421,7 +448,7 @@ impl Extern {
     /// Returns the size in bytes that the code representation will require.
     pub fn construct_code_str_length(&self) -> usize {
         13 + self.lib_name().len()
-            + if let Some(alias) = self.alias {
+            + if let Some(alias) = &self.alias {
                 4 + alias.len()
             } else {
                 0
@@ -486,7 +513,7 @@ mod tests {
         assert_eq!(&s, ans);
         assert_eq!(e.construct_code_str_length(), ans.len());
 
-        e.alias = Some("alias");
+        e.alias = Some("alias".to_string());
 
         let mut s = String::new();
         e.construct_code_str(&mut s);
@@ -12,7 +12,7 @@
 //!
 //! ```sh
 //! papyrus=> 2+2
-//! papyrus [out0]: 4
+//! papyrus [out0]: i32 = 4
 //! ```
 //!
 //! Papyrus is in active development, see [changelog](https://github.com/kurtlawrence/papyrus) for updates.
@@ -20,17 +20,29 @@
 //! ## Overview
 //! Papyrus creates a Rust REPL in your terminal. Code can be typed in, line by line with feedback on
 //! the evaluation, or code can be injected via stdin handles.
-//! Each code snippet is evaluated on an expression based system, so terminating with a semi-colon
-//! requires more input.
+//! Each code snippet is evaluated on an expression based system, so terminating with a `let`
+//! binding requires more input. Input also continues on the next line if it has unclosed delimiters,
+//! ends with an operator, or the line ends with `\`.
+//! With the `auto_add_crates` setting enabled, crates referenced by a `use` or a path, such as
+//! `use regex::Regex;`, are added as dependencies without needing `extern crate regex;`.
+//! Snippets can use `.await`, which runs on a small bundled executor or a configured runtime
+//! such as `tokio`.
+//! The `?` operator can be used at the top level, an `Err` is printed as an error and the input is
+//! not kept.
+//! The `papyrus` binary reads settings such as the prompt colours, compilation directory, and default
+//! dependencies from `~/.config/papyrus/config.toml`, a `.papyrus.toml` in the working directory
+//! overrides them.
+//! On startup `~/.papyrusrc.rs` and a `.papyrusrc.rs` in the working directory are loaded, their `use`
+//! imports are available in every module.
 //!
 //! ### Example
 //! ```sh
 //! [lib] papyrus=> 2+2
-//! papyrus [out0]: 4
-//! [lib] papyrus=> println!("Hello, world!");
-//! [lib] papyrus.> out0 * out0
+//! papyrus [out0]: i32 = 4
+//! [lib] papyrus=> let x = out0 * out0;
+//! [lib] papyrus.> println!("Hello, world!"); x
 //! Hello, world!
-//! papyrus [out1]: 16
+//! papyrus [out1]: i32 = 16
 //! [lib] papyrus=> :help
 //! help -- prints the help messages
 //! cancel | c -- returns to the root class
@@ -79,12 +91,37 @@ use papyrus::*;
 fn main() {
     windows_term_hack();
 
-    let repl = repl!();
+    let mut repl = repl!();
+
+    let config = config::Config::load().unwrap_or_else(|e| {
+        println!("warning: could not load config: {}", e);
+        config::Config::default()
+    });
+
+    if let Err(e) = repl.data.with_config(&config) {
+        println!("warning: could not apply config: {}", e);
+    }
+
+    if let Err(e) = repl.data.with_alias_config(config::user_config_path()) {
+        println!("warning: could not load aliases: {}", e);
+    }
 
     let app_data = &mut ();
 
-    let run_callbacks =
-        run::RunCallbacks::new(app_data).with_fmtrfn(run::fmt_based_on_terminal_width);
+    for path in config::startup_files() {
+        if let Err(e) = repl.data.load_startup_file(&path, app_data) {
+            println!("warning: failed to load `{}`: {}", path.display(), e);
+        }
+    }
+
+    let format_width = config.format_width;
+    let run_callbacks = run::RunCallbacks::new(app_data).with_fmtrfn(move |repl| {
+        let mut fmt = run::fmt_based_on_terminal_width(repl);
+        if format_width.is_some() {
+            fmt.width_limit = format_width;
+        }
+        fmt
+    });
 
     let output = repl.run(run_callbacks);
 
@@ -0,0 +1,257 @@
+use super::*;
+use std::io::Write;
+
+/// Separates the steps of an alias.
+const STEP_SEP: &str = ";;";
+
+impl<Data, S> ReplData<Data, S> {
+    /// Define a command alias. Invoking the alias as a command evaluates its steps in turn.
+    ///
+    /// Steps are separated by `;;`. A step beginning with `:` is a command, otherwise it is code.
+    /// For example `alpha` with steps `:mod switch scratch/alpha ;; let x = 1; x` switches module
+    /// and evaluates `x`. An alias with the same name is replaced.
+    ///
+    /// The command tree must have been set with
+    /// [`with_cmdtree_builder_fn`](ReplData::with_cmdtree_builder_fn) so it can be rebuilt.
+    pub fn with_alias(&mut self, name: &str, steps: &str) -> Result<&mut Self, AliasError> {
+        if self.cmdtree_builder.is_none() {
+            return Err(AliasError::NotRebuildable);
+        }
+
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(AliasError::InvalidName(name.to_string()));
+        }
+
+        let name = name.to_lowercase();
+        let is_cmd = matches!(name.as_str(), "help" | "cancel" | "c" | "exit")
+            || self
+                .cmdtree
+                .structure(true)
+                .iter()
+                .any(|x| x.path == name || x.path.strip_prefix("..") == Some(name.as_str()));
+        if is_cmd && !self.aliases.contains_key(&name) {
+            return Err(AliasError::Conflict(name));
+        }
+
+        let prev = self.aliases.insert(name.clone(), steps.trim().to_string());
+        if self.rebuild_cmdtree().is_err() {
+            match prev {
+                Some(prev) => self.aliases.insert(name.clone(), prev),
+                None => self.aliases.remove(&name),
+            };
+            self.rebuild_cmdtree().ok();
+            return Err(AliasError::Conflict(name));
+        }
+
+        Ok(self)
+    }
+
+    /// Remove a command alias, returning its steps.
+    pub fn remove_alias(&mut self, name: &str) -> Result<String, AliasError> {
+        if self.cmdtree_builder.is_none() {
+            return Err(AliasError::NotRebuildable);
+        }
+
+        let steps = self
+            .aliases
+            .remove(&name.to_lowercase())
+            .ok_or_else(|| AliasError::Unknown(name.to_string()))?;
+        self.rebuild_cmdtree().ok();
+        Ok(steps)
+    }
+
+    /// The command aliases as `(name, steps)`.
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Read command aliases from the `[aliases]` table of the configuration file, and save
+    /// aliases defined or removed with the `alias` command back to it.
+    ///
+    /// See [`config::user_config_path`](crate::config::user_config_path) for the user
+    /// configuration file.
+    pub fn with_alias_config<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, AliasError> {
+        let path = path.as_ref();
+        for (name, steps) in crate::config::read_aliases(path).map_err(AliasError::Config)? {
+            self.with_alias(&name, &steps)?;
+        }
+        self.alias_config = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Writes the aliases to the alias configuration file, if there is one.
+    pub(crate) fn save_aliases(&self) -> Result<(), AliasError> {
+        match &self.alias_config {
+            Some(path) => {
+                crate::config::write_aliases(path, &self.aliases).map_err(AliasError::Config)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Expands the alias into its steps, expanding any aliases invoked in the steps.
+    pub(crate) fn alias_steps(&self, name: &str) -> Result<Vec<String>, AliasError> {
+        let mut steps = Vec::new();
+        self.expand_alias(name, &mut Vec::new(), &mut steps)?;
+        Ok(steps)
+    }
+
+    fn expand_alias(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        steps: &mut Vec<String>,
+    ) -> Result<(), AliasError> {
+        if stack.iter().any(|x| x == name) {
+            return Err(AliasError::Recursive(stack[0].clone()));
+        }
+
+        let def = self
+            .aliases
+            .get(name)
+            .ok_or_else(|| AliasError::Unknown(name.to_string()))?;
+
+        stack.push(name.to_string());
+
+        for step in def.split(STEP_SEP).map(str::trim).filter(|x| !x.is_empty()) {
+            let alias = step
+                .strip_prefix(crate::CMD_PREFIX)
+                .and_then(|x| x.split_whitespace().next())
+                .map(str::to_lowercase)
+                .filter(|x| self.aliases.contains_key(x));
+
+            match alias {
+                Some(alias) => self.expand_alias(&alias, stack, steps)?,
+                None => steps.push(step.to_string()),
+            }
+        }
+
+        stack.pop();
+
+        Ok(())
+    }
+
+    /// Writes the help of the root class, listing the aliases under their own heading.
+    pub(crate) fn write_help_with_aliases<W: Write>(&mut self, writer: &mut W) {
+        let mut buf = Vec::new();
+        self.cmdtree.parse_line("help", true, &mut buf);
+
+        let helps = self
+            .aliases
+            .values()
+            .map(|x| format!(" -- {}", crate::cmds::alias_help(x)))
+            .collect::<Vec<_>>();
+
+        for line in String::from_utf8_lossy(&buf)
+            .lines()
+            .filter(|line| !helps.iter().any(|x| line.ends_with(x.as_str())))
+        {
+            writeln!(writer, "{}", line).ok();
+        }
+
+        writeln!(writer, "{}", "Aliases:".bright_purple()).ok();
+        for (name, steps) in &self.aliases {
+            writeln!(writer, "\t{} -- {}", name.bright_yellow(), steps).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_steps_test() {
+        let mut data = ReplData::<()>::default();
+        data.with_alias("a", ":mod switch a ;; 1 + 1").unwrap();
+        data.with_alias("b", ":a;; :mod switch lib ;;;; 2").unwrap();
+
+        assert_eq!(
+            data.alias_steps("b").unwrap(),
+            vec![":mod switch a", "1 + 1", ":mod switch lib", "2"]
+        );
+
+        data.with_alias("a", ":b").unwrap();
+        assert!(matches!(
+            data.alias_steps("b"),
+            Err(AliasError::Recursive(name)) if name == "b"
+        ));
+        assert!(matches!(data.alias_steps("c"), Err(AliasError::Unknown(_))));
+    }
+
+    #[test]
+    fn with_alias_test() {
+        let mut data = ReplData::<()>::default();
+
+        assert!(matches!(
+            data.with_alias("a b", "1"),
+            Err(AliasError::InvalidName(_))
+        ));
+        assert!(matches!(
+            data.with_alias("mut", "1"),
+            Err(AliasError::Conflict(_))
+        ));
+        assert!(matches!(
+            data.with_alias("edit", "1"),
+            Err(AliasError::Conflict(_))
+        ));
+        assert!(matches!(
+            data.with_alias("help", "1"),
+            Err(AliasError::Conflict(_))
+        ));
+
+        data.with_alias("Alpha", ":mod switch alpha").unwrap();
+        data.with_alias("alpha", ":mod switch beta").unwrap(); // replaces
+        assert_eq!(
+            data.aliases().collect::<Vec<_>>(),
+            vec![("alpha", ":mod switch beta")]
+        );
+        assert!(data
+            .cmdtree
+            .structure(true)
+            .iter()
+            .any(|x| x.path == "..alpha"));
+
+        assert_eq!(data.remove_alias("alpha").unwrap(), ":mod switch beta");
+        assert!(!data
+            .cmdtree
+            .structure(true)
+            .iter()
+            .any(|x| x.path == "..alpha"));
+        assert!(matches!(
+            data.remove_alias("alpha"),
+            Err(AliasError::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn aliases_need_rebuildable_cmdtree() {
+        use cmdtree::BuilderChain;
+
+        let custom = || {
+            Builder::new("papyrus")
+                .add_action("custom", "", |_, _| CommandResult::Empty)
+                .unwrap()
+        };
+        let has_cmd = |data: &ReplData<()>, path: &str| {
+            data.cmdtree.structure(true).iter().any(|x| x.path == path)
+        };
+
+        let mut data = ReplData::<()>::default();
+        data.with_cmdtree_builder(custom()).unwrap();
+        assert!(has_cmd(&data, "..custom"));
+        assert!(matches!(
+            data.with_alias("alpha", "1"),
+            Err(AliasError::NotRebuildable)
+        ));
+
+        data.with_cmdtree_builder_fn(custom).unwrap();
+        data.with_alias("alpha", "1").unwrap();
+        assert!(has_cmd(&data, "..custom"));
+        assert!(has_cmd(&data, "..alpha"));
+    }
+}
@@ -1,8 +1,8 @@
 use super::*;
 
 /// > **These methods are available in _any_ REPL state.**
-impl<S, D> Repl<S, D> {
-    pub(super) fn move_state<N, F: FnOnce(S) -> N>(self, state_chg: F) -> Repl<N, D> {
+impl<S, D, Sig> Repl<S, D, Sig> {
+    pub(super) fn move_state<N, F: FnOnce(S) -> N>(self, state_chg: F) -> Repl<N, D, Sig> {
         let Repl {
             state,
             data,
@@ -72,7 +72,7 @@ impl<S, D> Repl<S, D> {
     }
 }
 
-impl<S: fmt::Debug, D> fmt::Debug for Repl<S, D> {
+impl<S: fmt::Debug, D, Sig> fmt::Debug for Repl<S, D, Sig> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Repl in <{:?}> state instance", self.state)
     }
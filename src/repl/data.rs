@@ -1,9 +1,8 @@
 use super::*;
-use crate::code::{
-    parse_crates_in_file, validate_static_file_path, AddingStaticFileError, ModsMap, SourceCode,
-};
+use crate::code::{AddingStaticFileError, CrateType, ModsMap, SourceCode};
+use crate::config::{Config, ConfigError};
 
-impl<Data> Default for ReplData<Data> {
+impl<Data, S> Default for ReplData<Data, S> {
     fn default() -> Self {
         let lib_path = PathBuf::from("lib");
         let mut map = ModsMap::new();
@@ -13,27 +12,41 @@ impl<Data> Default for ReplData<Data> {
             cmdtree: Builder::new("papyrus")
                 .into_commander()
                 .expect("empty should pass"),
+            cmdtree_builder: None,
+            aliases: BTreeMap::new(),
+            alias_config: None,
+            arg_completers: BTreeMap::new(),
             mods_map: map,
             current_mod: lib_path,
             prompt_colour: Color::Cyan,
             out_colour: Color::BrightGreen,
+            show_result_type: true,
+            auto_add_crates: false,
             compilation_dir: default_compile_dir(),
             linking: LinkingConfiguration::default(),
             editing: None,
             editing_src: None,
+            mut_block: MutBlock::default(),
+            persisted_mut_blocks: Vec::new(),
+            clone_app_data: None,
+            eval_timings: Default::default(),
+            input_transforms: Vec::new(),
             static_files: StaticFiles::new(),
+            watch_static_files: false,
+            cancel: Default::default(),
             loadedlibs: VecDeque::new(),
             loaded_libs_size_limit: 0,
+            history_size: 100,
         };
 
-        r.with_cmdtree_builder(Builder::new("papyrus"))
+        r.with_cmdtree_builder_fn(|| Builder::new("papyrus"))
             .expect("should build fine");
 
         r
     }
 }
 
-impl<Data> ReplData<Data> {
+impl<Data, S> ReplData<Data, S> {
     /// Set the compilation directory. The default is set to `$HOME/.papyrus`.
     pub fn with_compilation_dir<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<&mut Self> {
         let dir = dir.as_ref();
@@ -45,19 +58,210 @@ impl<Data> ReplData<Data> {
         Ok(self)
     }
 
+    /// The directory for which compilation is done within.
+    pub fn compilation_dir(&self) -> &Path {
+        &self.compilation_dir
+    }
+
+    /// A handle to cancel a running task command, such as one returned by
+    /// [`CommandResult::task_fn`](crate::cmds::CommandResult::task_fn).
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Remove the linked external libraries with the library name or alias.
+    ///
+    /// Returns true if a library was removed.
+    pub fn remove_external_lib(&mut self, name: &str) -> bool {
+        let libs = &mut self.linking.external_libs;
+        let len = libs.len();
+        libs.retain(|x| x.lib_name() != name && x.alias() != Some(name));
+        libs.len() != len
+    }
+
     /// Link an external library.
     ///
     /// This is primarily used for linking the calling library, and there
     /// is a function on `Extern` to work this path out. It is better to
     /// use `crates.io` than linking libraries, but this method allows for
-    /// linking libraries not on `crates.io`.
+    /// linking libraries not on `crates.io`. A library with the same path is replaced.
     ///
     /// [See _linking_ module](../pfh/linking.html)
     pub fn with_external_lib(&mut self, lib: linking::Extern) -> &mut Self {
-        self.linking.external_libs.insert(lib);
+        self.linking.external_libs.replace(lib);
+        self
+    }
+
+    /// Map a crate name, as used in Rust, to the package name to use in cargo.
+    ///
+    /// For example `with_crate_name("proc_macro2", "proc-macro2")`. See
+    /// [`LinkingConfiguration::crate_names`].
+    pub fn with_crate_name(&mut self, name: &str, cargo_name: &str) -> &mut Self {
+        self.linking
+            .crate_names
+            .insert(name.to_string(), cargo_name.to_string());
+        self
+    }
+
+    /// Use a runtime to run REPL code which uses `.await`, rather than the bundled executor.
+    ///
+    /// `block_on` is a function which accepts a future and returns its output, for example
+    /// `tokio::runtime::Runtime::new().unwrap().block_on`. The runtime's crate needs to be
+    /// referenced in the REPL. See [`LinkingConfiguration::async_runtime`].
+    pub fn with_async_runtime(&mut self, block_on: &str) -> &mut Self {
+        self.linking.async_runtime = Some(block_on.to_string());
+        self
+    }
+
+    /// Creates the REPL data with the settings of the configuration.
+    ///
+    /// See [`ReplData::with_config`].
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let mut data = Self::default();
+        data.with_config(config)?;
+        Ok(data)
+    }
+
+    /// Apply the settings of the configuration, settings which are not specified are left as is.
+    ///
+    /// Dependencies are added as crates of the `lib` module, static files are read and imported,
+    /// and the persistent module code is appended, skipping lines which are already present. The
+    /// formatting width is not held by the REPL data, use it when
+    /// [formatting results](crate::run::RunCallbacks::with_fmtrfn).
+    pub fn with_config(&mut self, config: &Config) -> Result<&mut Self, ConfigError> {
+        if let Some(colour) = config.prompt_colour {
+            self.prompt_colour = colour;
+        }
+        if let Some(colour) = config.out_colour {
+            self.out_colour = colour;
+        }
+        if let Some(limit) = config.loaded_libs_size_limit {
+            self.loaded_libs_size_limit = limit;
+        }
+        if let Some(size) = config.history_size {
+            self.history_size = size;
+        }
+        if let Some(auto) = config.auto_add_crates {
+            self.auto_add_crates = auto;
+        }
+        if let Some(dir) = &config.compilation_dir {
+            self.with_compilation_dir(dir).map_err(ConfigError::Io)?;
+        }
+
+        for dep in &config.dependencies {
+            let name = dep.replace('-', "_");
+            let cr = CrateType::parse_str(&format!("extern crate {};", name))
+                .map_err(|_| ConfigError::Invalid("dependencies".to_string(), "crate names"))?;
+            if name != *dep {
+                self.with_crate_name(&name, dep);
+            }
+            let lib = self
+                .mods_map
+                .get_mut(Path::new("lib"))
+                .expect("lib always exists");
+            if !lib.crates.contains(&cr) {
+                lib.crates.push(cr);
+            }
+        }
+
+        for pattern in &config.static_files {
+            let paths = glob::glob(pattern).map_err(|_| {
+                ConfigError::Invalid("static_files".to_string(), "file paths or glob patterns")
+            })?;
+            for path in paths.filter_map(Result::ok) {
+                fs::read_to_string(&path)
+                    .map_err(AddingStaticFileError::Io)
+                    .and_then(|code| self.add_static_file(path.clone(), &code))
+                    .map_err(|e| ConfigError::StaticFile(path, e))?;
+            }
+        }
+
+        if let Some(code) = &config.persistent_module_code {
+            // lines already present are skipped, so applying a config again is idempotent
+            let persistent = &mut self.linking.persistent_module_code;
+            let lines = code
+                .lines()
+                .filter(|x| !persistent.lines().any(|line| line.trim() == x.trim()))
+                .collect::<Vec<_>>();
+            if !lines.is_empty() {
+                if !persistent.is_empty() && !persistent.ends_with('\n') {
+                    persistent.push('\n');
+                }
+                persistent.push_str(&lines.join("\n"));
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Add a transform which can rewrite or reject code input before it is parsed.
+    ///
+    /// Transforms are applied in the order they are added, each receiving the output of the
+    /// previous one, once the input is complete. Commands are not transformed. Rewrites are
+    /// reported in the output.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use papyrus::repl::{ReplData, Transformed};
+    /// let mut data = ReplData::<()>::default();
+    /// data.with_input_transform(|input| match input.strip_prefix('@') {
+    ///     Some(user) => Transformed::Rewritten(format!("app_data.users.get({:?})", user)),
+    ///     None => Transformed::Unchanged,
+    /// });
+    /// ```
+    pub fn with_input_transform<F>(&mut self, transform: F) -> &mut Self
+    where
+        F: 'static + Fn(&str) -> Transformed + Send,
+    {
+        self.input_transforms.push(Box::new(transform));
         self
     }
 
+    /// Complete the arguments of a command action with `completer`.
+    ///
+    /// `action` is the qualified path of the action, classes are separated by `.` and the action
+    /// by `..`, such as `case..upper`, or `..echo` for an action on the root. The completer is
+    /// given the arguments of the line and returns the completions of the final word. A completer
+    /// for the same action is replaced.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use papyrus::repl::ReplData;
+    /// let mut data = ReplData::<()>::default();
+    /// data.with_arg_completer("..echo", |arg| {
+    ///     vec!["hello", "world"]
+    ///         .into_iter()
+    ///         .filter(|x| x.starts_with(arg.word))
+    ///         .map(String::from)
+    ///         .collect()
+    /// });
+    /// ```
+    pub fn with_arg_completer<F>(&mut self, action: &str, completer: F) -> &mut Self
+    where
+        F: 'static + Fn(&ArgComplete) -> Vec<String> + Send,
+    {
+        self.arg_completers
+            .insert(action.to_string(), Box::new(completer));
+        self
+    }
+
+    /// Applies the input transforms in order.
+    ///
+    /// Returns the rewritten input if any transform rewrote it, or the reason if rejected.
+    pub(super) fn transform_input(&self, input: &str) -> Result<Option<String>, String> {
+        let mut rewritten: Option<String> = None;
+
+        for transform in &self.input_transforms {
+            match transform(rewritten.as_deref().unwrap_or(input)) {
+                Transformed::Unchanged => (),
+                Transformed::Rewritten(s) => rewritten = Some(s),
+                Transformed::Rejected(reason) => return Err(reason),
+            }
+        }
+
+        Ok(rewritten)
+    }
+
     /// The current mod that is being repl'd on.
     pub fn current_mod(&self) -> &Path {
         self.current_mod.as_path()
@@ -105,31 +309,24 @@ impl<Data> ReplData<Data> {
         path: PathBuf,
         code: &str,
     ) -> Result<bool, AddingStaticFileError> {
-        validate_static_file_path(&path).map_err(AddingStaticFileError::InvalidPath)?;
-
-        let hash: [u8; 32] = blake3::hash(code.as_bytes()).into();
+        // parse for crates
+        let (file, code) = StaticFile::parse(path, code)?;
 
         let change = {
             self.static_files
-                .get(path.as_path())
-                .map(|sf| sf.codehash.as_ref() != &hash)
+                .get(file.path.as_path())
+                .map(|sf| sf.codehash != file.codehash)
                 .unwrap_or(true)
         };
 
         if change {
-            // parse for crates
-            let (code, crates) = parse_crates_in_file(code);
             // write remaining code to disk
-            let file_name = self.static_file_name(&path);
+            let file_name = self.static_file_name(&file.path);
             let parent = file_name.parent().expect("should exist");
             fs::create_dir_all(parent).map_err(AddingStaticFileError::Io)?;
             fs::write(file_name, code).map_err(AddingStaticFileError::Io)?;
             // add/overwrite in set
-            self.static_files.insert(StaticFile {
-                path,
-                codehash: Box::new(hash),
-                crates,
-            });
+            self.static_files.replace(file);
         }
 
         Ok(change)
@@ -147,7 +344,40 @@ impl<Data> ReplData<Data> {
         removed
     }
 
-    fn static_file_name(&self, path: &Path) -> PathBuf {
+    /// Read the static files from disk again, reloading the files which changed.
+    ///
+    /// Returns the paths of the files which were reloaded, or which failed to reload. A file which
+    /// no longer exists is removed, such that it is only reported once with a
+    /// [`NotFound`](io::ErrorKind::NotFound) error.
+    pub fn reload_static_files(&mut self) -> Vec<(PathBuf, Result<(), AddingStaticFileError>)> {
+        let paths = self
+            .static_files
+            .iter()
+            .map(|x| x.path.clone())
+            .collect::<Vec<_>>();
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let r = match fs::read_to_string(&path) {
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        self.remove_static_file(&path);
+                        Err(AddingStaticFileError::Io(e))
+                    }
+                    r => r
+                        .map_err(AddingStaticFileError::Io)
+                        .and_then(|code| self.add_static_file(path.clone(), &code)),
+                };
+                match r {
+                    Ok(false) => None,
+                    Ok(true) => Some((path, Ok(()))),
+                    Err(e) => Some((path, Err(e))),
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn static_file_name(&self, path: &Path) -> PathBuf {
         self.compilation_dir.join("src").join(path)
     }
 
@@ -161,6 +391,28 @@ impl<Data> ReplData<Data> {
         self.loadedlibs.clear()
     }
 
+    /// Enable transactional mutable blocks, `:mut tx`.
+    ///
+    /// The app data is cloned before a transactional mutable block is evaluated, and restored if
+    /// the block fails or panics, such that a block does not leave the data partially mutated.
+    pub fn with_transactional_mut(&mut self) -> &mut Self
+    where
+        Data: Clone,
+    {
+        self.clone_app_data = Some(Data::clone);
+        self
+    }
+
+    /// Snapshot the app data, if transactional mutable blocks are enabled.
+    pub(super) fn snapshot_app_data(&self, app_data: &Data) -> Option<Data> {
+        self.clone_app_data.map(|f| f(app_data))
+    }
+
+    /// Is transactional mutable blocks enabled.
+    pub fn transactional_mut(&self) -> bool {
+        self.clone_app_data.is_some()
+    }
+
     /// Not meant to used by developer. Use the macros instead.
     /// [See _linking_ module](../pfh/linking.html)
     ///
@@ -202,4 +454,85 @@ mod tests {
         data.add_static_file("path/to/something.rs".into(), "")
             .unwrap();
     }
+
+    #[test]
+    fn reload_static_files_test() {
+        let dir = Path::new("target/testing/reload_static_files");
+        fs::create_dir_all(dir).unwrap();
+        let one = dir.join("one.rs");
+        let two = dir.join("two.rs");
+        fs::write(&one, "pub fn one() -> i32 { 1 }").unwrap();
+        fs::write(&two, "pub fn two() -> i32 { 2 }").unwrap();
+
+        let mut data = ReplData::<()>::default();
+        data.with_compilation_dir(dir.join("compile")).unwrap();
+        data.add_static_file(one.clone(), "pub fn one() -> i32 { 1 }")
+            .unwrap();
+        data.add_static_file(two.clone(), "pub fn two() -> i32 { 2 }")
+            .unwrap();
+
+        assert!(data.reload_static_files().is_empty()); // unchanged
+
+        fs::write(&two, "pub fn two() -> i32 { 22 }").unwrap();
+        let r = data.reload_static_files();
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].0, two);
+        assert!(r[0].1.is_ok());
+        assert!(data.reload_static_files().is_empty());
+
+        fs::remove_file(&one).unwrap();
+        let r = data.reload_static_files();
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].0, one);
+        assert!(matches!(r[0].1, Err(AddingStaticFileError::Io(_))));
+        // deleted files are removed, reporting once
+        assert_eq!(data.static_files().len(), 1);
+        assert!(data.reload_static_files().is_empty());
+    }
+
+    #[test]
+    fn with_config_test() {
+        let dir = Path::new("target/testing/config_apply");
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("helpers.rs"), "pub fn one() -> i32 { 1 }").unwrap();
+
+        let config = Config {
+            compilation_dir: Some(dir.join("compile")),
+            out_colour: Some(Color::Green),
+            history_size: Some(20),
+            auto_add_crates: Some(true),
+            dependencies: vec!["proc-macro2".to_string()],
+            static_files: vec!["target/testing/config_apply/*.rs".to_string()],
+            persistent_module_code: Some("use std::fmt;".to_string()),
+            ..Config::default()
+        };
+
+        let mut data = ReplData::<()>::from_config(&config).unwrap();
+        data.persistent_module_code().push_str("\nuse std::io;");
+        data.with_config(&config).unwrap();
+
+        assert_eq!(data.out_colour, Color::Green);
+        assert_eq!(data.history_size, 20);
+        assert!(data.auto_add_crates);
+        assert_eq!(
+            data.mods_map()[Path::new("lib")]
+                .crates
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["proc_macro2"]
+        );
+        assert_eq!(
+            data.linking()
+                .crate_names
+                .get("proc_macro2")
+                .map(String::as_str),
+            Some("proc-macro2")
+        );
+        assert_eq!(data.static_files().len(), 1);
+        assert_eq!(
+            data.linking().persistent_module_code,
+            "use std::fmt;\nuse std::io;"
+        );
+    }
 }
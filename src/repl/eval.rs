@@ -1,18 +1,19 @@
 use super::*;
 use crate::{
     cmds::{self, CommandResult},
-    code::{self, Input, SourceCode, StmtGrp},
+    code::{self, CrateType, Input, SourceCode, StmtGrp},
     compile,
 };
 use std::borrow::{Borrow, BorrowMut};
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// > **These methods are available when the REPL is in the [`Evaluate`] state.**
-impl<D> Repl<Evaluate, D> {
+impl<D, S> Repl<Evaluate, D, S> {
     /// Evaluates the read input, compiling and executing the code and printing all line prints until
     /// a result is found. This result gets passed back as a print ready repl.
-    pub fn eval(self, app_data: &mut D) -> EvalResult<D> {
+    pub fn eval(self, app_data: &mut D) -> EvalResult<D, S> {
         use std::cell::Cell;
         use std::rc::Rc;
 
@@ -38,25 +39,28 @@ impl<D> Repl<Evaluate, D> {
                 .unwrap()
         };
 
-        map_variants(self, func, func)
+        map_variants(self, func, func, None)
     }
 
     /// Same as `eval` but will evaluate on another thread, not blocking this one.
     ///
     /// An `Arc::clone` will be taken of `app_data`.
-    pub fn eval_async(self, app_data: &Arc<Mutex<D>>) -> Evaluating<D>
+    pub fn eval_async(self, app_data: &Arc<Mutex<D>>) -> Evaluating<D, S>
     where
         D: 'static + Send,
+        S: 'static + Send,
     {
         let (tx, rx) = crossbeam_channel::bounded(1);
 
         let clone = Arc::clone(app_data);
 
         std::thread::spawn(move || {
+            let shared: SharedData = Arc::clone(&clone) as _;
             let eval = map_variants(
                 self,
                 || clone.lock().expect("failed getting lock of data"),
                 || clone.lock().expect("failed getting lock of data"),
+                Some(&shared),
             );
 
             tx.send(eval).unwrap();
@@ -83,7 +87,7 @@ impl<D> Repl<Evaluate, D> {
     }
 }
 
-impl<D> Evaluating<D> {
+impl<D, S> Evaluating<D, S> {
     /// Evaluating has finished.
     pub fn completed(&self) -> bool {
         !self.jh.is_empty()
@@ -91,18 +95,22 @@ impl<D> Evaluating<D> {
 
     /// Waits for the evaluating to finish before return the result.
     /// If evaluating is `completed` this will return immediately.
-    pub fn wait(self) -> EvalResult<D> {
+    pub fn wait(self) -> EvalResult<D, S> {
         self.jh
             .recv()
             .expect("receiving eval result from async thread failed")
     }
 }
 
-fn map_variants<D, Fmut, Fbrw, Rmut, Rbrw>(
-    repl: Repl<Evaluate, D>,
+/// The app data shared with tasks when evaluating asynchronously.
+type SharedData = Arc<dyn std::any::Any + Send + Sync>;
+
+fn map_variants<D, S, Fmut, Fbrw, Rmut, Rbrw>(
+    repl: Repl<Evaluate, D, S>,
     obtain_mut_data: Fmut,
     obtain_brw_data: Fbrw,
-) -> EvalResult<D>
+    shared: Option<&SharedData>,
+) -> EvalResult<D, S>
 where
     Fmut: FnOnce() -> Rmut,
     Rmut: DerefMut<Target = D>,
@@ -118,19 +126,34 @@ where
 
     let Evaluate { mut output, result } = state;
 
+    if data.watch_static_files
+        && matches!(result, InputResult::Command(_) | InputResult::Program(_))
+    {
+        for (path, r) in data.reload_static_files() {
+            let line = match r {
+                Ok(()) => format!("reloaded static file `{}`", path.display()),
+                Err(code::AddingStaticFileError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                    format!("removed static file `{}` as it was deleted", path.display())
+                }
+                Err(e) => format!("failed to reload static file `{}`: {}", path.display(), e),
+            };
+            output.write_line(&line);
+        }
+    }
+
     let mut keep_mutating = false; // default to stop mutating phase
                                    // can't cancel before as handle program requires it for decisions
 
     // map variants into Result<HandleInputResult, EvalSignal>
     let mapped = match result {
         InputResult::Command(cmds) => {
-            let r = data.handle_command(&cmds, &mut output, obtain_mut_data);
+            let r = data.handle_command(&cmds, &mut output, obtain_mut_data, shared);
             keep_mutating = data.linking.mutable; // a command can alter the mutating state, needs to persist
-            r.map(EvalOutput::Print)
-        }
-        InputResult::Program(input) => {
-            Ok(data.handle_program(input, &mut output, obtain_mut_data, obtain_brw_data))
+            r
         }
+        InputResult::Program(input) => Ok(data
+            .handle_program(input, &mut output, false, obtain_mut_data, obtain_brw_data)
+            .unwrap_or_else(EvalOutput::Print)),
         InputResult::InputError(err) => Ok(EvalOutput::Print(Cow::Owned(err))),
         InputResult::Eof => Err(Signal::Exit),
         _ => Ok(EvalOutput::Print(Cow::Borrowed(""))),
@@ -160,20 +183,410 @@ where
     }
 }
 
-impl<D> ReplData<D> {
-    fn handle_command<F, R, W>(
+impl<D, S> ReplData<D, S> {
+    /// Load a rust source file as if it was entered as REPL input.
+    ///
+    /// The file is split into crates, items, and statement groups (see
+    /// [`parse_file`](crate::input::parse_file)), which are evaluated in turn into the current
+    /// module. Evaluation stops at the first failing input. Returns the number of inputs evaluated.
+    pub fn load_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        app_data: &mut D,
+    ) -> Result<usize, LoadError> {
+        let mut output = Output::new().into_write();
+        self.load_file_inner(path.as_ref(), &mut output, app_data)
+    }
+
+    /// Load a startup file, such as `.papyrusrc.rs`, into the REPL.
+    ///
+    /// The `use` imports are appended to the [persistent module
+    /// code](ReplData::persistent_module_code) so they are available in every module. The
+    /// remaining crates, items, and statements are evaluated into the `lib` module as a single
+    /// program. If the imports fail to compile they are removed again.
+    ///
+    /// Startup files are not loaded automatically, the `papyrus` binary loads
+    /// [`config::startup_files`](crate::config::startup_files) before running the REPL.
+    pub fn load_startup_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        app_data: &mut D,
+    ) -> Result<(), LoadError> {
+        let code = fs::read_to_string(path).map_err(LoadError::Io)?;
+        let inputs = crate::input::parse_file(&code).map_err(LoadError::Parse)?;
+
+        let mut imports = Vec::new();
+        let mut program = Input {
+            items: Vec::new(),
+            stmts: Vec::new(),
+            crates: Vec::new(),
+        };
+        for input in inputs {
+            let (uses, items): (Vec<code::Item>, _) = input
+                .items
+                .into_iter()
+                .partition(|x| syn::parse_str::<syn::ItemUse>(&x.0).is_ok());
+            imports.extend(uses.into_iter().map(|x| x.0));
+            program.items.extend(items);
+            program.crates.extend(input.crates);
+            // only the last expression of the program is returned
+            if let (Some(last), false) = (program.stmts.last_mut(), input.stmts.is_empty()) {
+                last.semi = true;
+            }
+            program.stmts.extend(input.stmts);
+        }
+
+        let mut output = Output::new().into_write();
+        let current = std::mem::replace(&mut self.current_mod, PathBuf::from("lib"));
+        let r = self.load_startup_inputs(imports, program, &mut output, app_data);
+        self.current_mod = current;
+
+        r
+    }
+
+    fn load_startup_inputs(
+        &mut self,
+        imports: Vec<String>,
+        program: Input,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Result<(), LoadError> {
+        let prev = self.linking.persistent_module_code.clone();
+        let imports = imports
+            .into_iter()
+            .filter(|x| !prev.lines().any(|line| line.trim() == x.trim()))
+            .collect::<Vec<_>>();
+
+        if !imports.is_empty() {
+            let persistent = &mut self.linking.persistent_module_code;
+            if !persistent.is_empty() && !persistent.ends_with('\n') {
+                persistent.push('\n');
+            }
+            persistent.push_str(&imports.join("\n"));
+
+            // check the imports compile before evaluating anything else
+            let trial = parse_transient_expr("()", "load").expect("unit is an expression");
+            if let Err(e) = self.handle_program_with_data(trial, writer, app_data, true) {
+                self.linking.persistent_module_code = prev;
+                return Err(LoadError::Eval(imports.join("\n"), e.into_owned()));
+            }
+        }
+
+        if program.items.is_empty() && program.crates.is_empty() && program.stmts.is_empty() {
+            return Ok(());
+        }
+
+        let desc = describe_input(&program);
+        self.handle_program_with_data(program, writer, app_data, false)
+            .map(|_| ())
+            .map_err(|e| LoadError::Eval(desc, e.into_owned()))
+    }
+
+    fn load_file_inner(
+        &mut self,
+        path: &Path,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Result<usize, LoadError> {
+        let code = fs::read_to_string(path).map_err(LoadError::Io)?;
+        let inputs = crate::input::parse_file(&code).map_err(LoadError::Parse)?;
+
+        let mut count = 0;
+
+        for input in inputs {
+            let desc = describe_input(&input);
+
+            self.handle_program_with_data(input, writer, app_data, false)
+                .map_err(|e| LoadError::Eval(desc, e.into_owned()))?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Evaluates the steps of an alias in turn, stopping at the first code step which fails.
+    fn run_alias(
+        &mut self,
+        name: &str,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+        shared: Option<&SharedData>,
+    ) -> Result<Cow<'static, str>, Signal<S>> {
+        let steps = match self.alias_steps(name) {
+            Ok(steps) => steps,
+            Err(e) => return Ok(Cow::Owned(e.to_string())),
+        };
+
+        let mut out = Cow::Borrowed("");
+
+        for step in steps {
+            if !out.is_empty() {
+                writer.write_line(&out);
+            }
+
+            let r = match step.strip_prefix(crate::CMD_PREFIX) {
+                Some(cmds) => {
+                    let app_data = &mut *app_data;
+                    Ok(self.handle_command(cmds, writer, move || app_data, shared)?)
+                }
+                None => match crate::input::parse_program(&step) {
+                    InputResult::Program(input) => {
+                        self.handle_program_with_data(input, writer, app_data, false)
+                    }
+                    InputResult::InputError(e) => Err(Cow::Owned(e)),
+                    _ => Err(Cow::Borrowed("incomplete input")),
+                },
+            };
+
+            out = match r {
+                Ok(EvalOutput::Print(s)) => s,
+                Ok(EvalOutput::Data(kserd, _)) | Ok(EvalOutput::Value(kserd, _)) => {
+                    Cow::Owned(kserd.as_str())
+                }
+                Err(e) => return Ok(Cow::Owned(format!("alias step `{}` failed: {}", step, e))),
+            };
+        }
+
+        Ok(out)
+    }
+
+    fn begin_mut_block(&mut self, opts: MutBlock) -> Cow<'static, str> {
+        if opts.transactional && !self.transactional_mut() {
+            return Cow::Borrowed(
+                "transactional mut blocks are not enabled, see `ReplData::with_transactional_mut`",
+            );
+        }
+
+        self.linking.mutable = true;
+        self.mut_block = opts;
+        Cow::Borrowed("beginning mut block")
+    }
+
+    /// Evaluates a persisted mutable block again, in the module it was first evaluated in.
+    fn replay_mut_block(
+        &mut self,
+        idx: usize,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Cow<'static, str> {
+        let (path, input, opts) = match self.persisted_mut_blocks.get(idx) {
+            Some(x) => x.clone(),
+            None => return Cow::Borrowed("index is outside of range"),
+        };
+
+        if !self.mods_map.contains_key(&path) {
+            return Cow::Owned(format!("module `{}` no longer exists", path.display()));
+        }
+
+        let current = std::mem::replace(&mut self.current_mod, path);
+        self.linking.mutable = true;
+        self.mut_block = MutBlock {
+            persist: false, // already persisted
+            ..opts
+        };
+
+        let r = self.handle_program_with_data(input, writer, app_data, false);
+
+        self.linking.mutable = false;
+        self.current_mod = current;
+
+        match r {
+            Ok(EvalOutput::Print(s)) | Err(s) => s,
+            Ok(EvalOutput::Data(kserd, _)) | Ok(EvalOutput::Value(kserd, _)) => {
+                Cow::Owned(kserd.as_str())
+            }
+        }
+    }
+
+    /// Evaluates the expression without keeping it, returning its type name.
+    fn type_of(
+        &mut self,
+        expr: &str,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Cow<'static, str> {
+        let input = match parse_transient_expr(expr, "type") {
+            Ok(input) => input,
+            Err(e) => return e,
+        };
+
+        match self.handle_program_with_data(input, writer, app_data, true) {
+            Ok(EvalOutput::Data(_, ty)) => Cow::Owned(ty),
+            Ok(EvalOutput::Value(kserd, _)) => Cow::Owned(kserd.as_str()),
+            Ok(EvalOutput::Print(s)) | Err(s) => s,
+        }
+    }
+
+    /// Evaluates the expression without keeping it, returning its type and result alongside the
+    /// time taken to compile and to execute it.
+    fn time_expr(
+        &mut self,
+        expr: &str,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Result<Kserd<'static>, Cow<'static, str>> {
+        let input = parse_transient_expr(expr, "time")?;
+
+        match self.handle_program_with_data(input, writer, app_data, true) {
+            Ok(EvalOutput::Data(kserd, ty)) => {
+                let (compile, exec) = self.eval_timings;
+                Kserd::new_cntr(vec![
+                    ("type", Kserd::new_string(ty)),
+                    ("value", kserd),
+                    ("compile", duration_kserd(compile)),
+                    ("execution", duration_kserd(exec)),
+                ])
+                .map_err(|e| Cow::Owned(e.to_string()))
+            }
+            Ok(EvalOutput::Value(kserd, _)) => Ok(kserd),
+            Ok(EvalOutput::Print(s)) | Err(s) => Err(s),
+        }
+    }
+
+    /// Evaluates the expression `iterations` times in a loop without keeping it, returning the
+    /// mean, median, min, and max execution time of an iteration.
+    ///
+    /// The expression is evaluated in each iteration, so it can not move out of a local which is
+    /// not `Copy`, such as `:bench drop(s)`.
+    fn bench_expr(
+        &mut self,
+        expr: &str,
+        iterations: usize,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+    ) -> Result<Kserd<'static>, Cow<'static, str>> {
+        parse_transient_expr(expr, "bench")?;
+
+        // black_box stops the expression from being optimised away, times are in nanoseconds
+        let code = format!(
+            "let __papyrus_bench = {{
+let mut __papyrus_times = Vec::with_capacity({n});
+for _ in 0..{n} {{
+let __papyrus_start = std::time::Instant::now();
+std::hint::black_box({{ {expr} }});
+__papyrus_times.push(__papyrus_start.elapsed().as_nanos() as u64);
+}}
+__papyrus_times.sort_unstable();
+let __papyrus_len = __papyrus_times.len();
+(__papyrus_times.iter().sum::<u64>() / __papyrus_len as u64, __papyrus_times[__papyrus_len / 2], __papyrus_times[0], __papyrus_times[__papyrus_len - 1])
+}};
+__papyrus_bench",
+            n = iterations,
+            expr = expr
+        );
+
+        let input = parse_transient_expr(&code, "bench")?;
+
+        match self.handle_program_with_data(input, writer, app_data, true) {
+            Ok(EvalOutput::Data(kserd, _)) => {
+                let times = kserd
+                    .tuple()
+                    .map(|x| {
+                        x.iter()
+                            .filter_map(|x| x.uint())
+                            .map(|x| duration_kserd(Duration::from_nanos(x as u64)))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                if times.len() != 4 {
+                    return Err(Cow::Borrowed("failed to read the benchmark times"));
+                }
+
+                let fields = ["mean", "median", "min", "max"].iter().copied().zip(times);
+                Kserd::new_cntr(
+                    std::iter::once(("iterations", Kserd::new_num(iterations))).chain(fields),
+                )
+                .map_err(|e| Cow::Owned(e.to_string()))
+            }
+            Ok(EvalOutput::Value(kserd, _)) => Ok(kserd),
+            Ok(EvalOutput::Print(s)) | Err(s) => Err(s),
+        }
+    }
+
+    /// Runs the task action on a worker thread, writing its progress lines until it finishes or
+    /// is cancelled.
+    fn run_task(
+        &mut self,
+        action: cmds::TaskAction,
+        writer: &mut Output<output::Write>,
+        shared: Option<&SharedData>,
+    ) -> Cow<'static, str> {
+        use crossbeam_channel::select;
+
+        let (task, progress) = self.cancel.begin(shared.cloned());
+        let (tx, rx) = crossbeam_channel::bounded(1);
+
+        std::thread::spawn(move || {
+            tx.send(action(&task)).ok();
+        });
+
+        let r = loop {
+            if self.cancel.cancelled() {
+                break Cow::Borrowed("task cancelled");
+            }
+
+            select! {
+                recv(progress) -> line => if let Ok(line) = line {
+                    writer.write_line(&line);
+                },
+                recv(rx) -> r => {
+                    for line in progress.try_iter() {
+                        writer.write_line(&line);
+                    }
+                    if !self.cancel.cancelled() {
+                        break r.map(Cow::Owned).unwrap_or(Cow::Borrowed("task panicked"));
+                    }
+                },
+                default(Duration::from_millis(10)) => (),
+            }
+        };
+
+        self.cancel.finish();
+
+        r
+    }
+
+    /// Handles the program with direct access to the app data.
+    fn handle_program_with_data(
+        &mut self,
+        input: Input,
+        writer: &mut Output<output::Write>,
+        app_data: &mut D,
+        transient: bool,
+    ) -> Result<EvalOutput, Cow<'static, str>> {
+        use std::cell::{Ref, RefCell, RefMut};
+
+        let cell = RefCell::new(app_data);
+
+        self.handle_program(
+            input,
+            writer,
+            transient,
+            || RefMut::map(cell.borrow_mut(), |x| &mut **x),
+            || Ref::map(cell.borrow(), |x| &**x),
+        )
+    }
+
+    fn handle_command<F, R>(
         &mut self,
         cmds: &str,
-        writer: &mut W,
+        writer: &mut Output<output::Write>,
         obtain_mut_app_data: F,
-    ) -> Result<Cow<'static, str>, Signal>
+        shared: Option<&SharedData>,
+    ) -> Result<EvalOutput, Signal<S>>
     where
         F: FnOnce() -> R,
         R: DerefMut<Target = D>,
-        W: io::Write,
     {
         use cmdtree::LineResult as lr;
 
+        if self.cmdtree.at_root() && cmds.trim() == "help" && !self.aliases.is_empty() {
+            self.write_help_with_aliases(writer);
+            return Ok(EvalOutput::Print(Cow::Borrowed("")));
+        }
+
         let tuple = match self.cmdtree.parse_line(cmds, true, writer) {
             lr::Exit => return Err(Signal::Exit),
             lr::Cancel => {
@@ -182,9 +595,12 @@ impl<D> ReplData<D> {
                 Cow::Borrowed("cancelled input and returned to root")
             }
             lr::Action(res) => match res {
-                CommandResult::BeginMutBlock => {
-                    self.linking.mutable = true;
-                    Cow::Borrowed("beginning mut block")
+                CommandResult::BeginMutBlock => self.begin_mut_block(MutBlock::default()),
+                CommandResult::BeginMutBlockWith(opts) => self.begin_mut_block(opts),
+                CommandResult::ReplayMutBlock(idx) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    self.replay_mut_block(idx, writer, app_data)
                 }
                 CommandResult::EditAlter(ei) => Cow::Borrowed(cmds::edit_alter(self, ei)),
                 CommandResult::EditReplace(ei, val) => {
@@ -207,27 +623,81 @@ impl<D> ReplData<D> {
                     let s = action(app_data, self, writer);
                     Cow::Owned(s)
                 }
+                CommandResult::ActionWithValue(action) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    let kserd = action(app_data, self, writer);
+                    return Ok(EvalOutput::Value(kserd, cmds.trim().to_string()));
+                }
+                CommandResult::RunTask(action) => self.run_task(action, writer, shared),
+                CommandResult::CustomSignal(payload) => return Err(Signal::Custom(payload)),
+                CommandResult::TypeOf(expr) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    self.type_of(&expr, writer, app_data)
+                }
+                CommandResult::Time(expr) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    match self.time_expr(&expr, writer, app_data) {
+                        Ok(kserd) => return Ok(EvalOutput::Value(kserd, cmds.trim().to_string())),
+                        Err(e) => e,
+                    }
+                }
+                CommandResult::Bench(expr, iterations) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    match self.bench_expr(&expr, iterations, writer, app_data) {
+                        Ok(kserd) => return Ok(EvalOutput::Value(kserd, cmds.trim().to_string())),
+                        Err(e) => e,
+                    }
+                }
+                CommandResult::RunAlias(name) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    self.run_alias(&name, writer, app_data, shared)?
+                }
+                CommandResult::LoadFile(path) => {
+                    let mut r = obtain_mut_app_data();
+                    let app_data: &mut D = r.borrow_mut();
+                    match self.load_file_inner(&path, writer, app_data) {
+                        Ok(n) => {
+                            Cow::Owned(format!("loaded {} inputs from `{}`", n, path.display()))
+                        }
+                        Err(e) => Cow::Owned(format!("{}", e)),
+                    }
+                }
                 CommandResult::Empty => Cow::Borrowed(""),
             },
             _ => Cow::Borrowed(""),
         };
 
-        Ok(tuple)
+        Ok(EvalOutput::Print(tuple))
     }
 
     fn handle_program<Fmut, Fbrw, Rmut, Rbrw>(
         &mut self,
         mut input: Input,
         writer: &mut Output<output::Write>,
+        transient: bool,
         obtain_mut_data: Fmut,
         obtain_brw_data: Fbrw,
-    ) -> EvalOutput
+    ) -> Result<EvalOutput, Cow<'static, str>>
     where
         Fmut: FnOnce() -> Rmut,
         Rmut: DerefMut<Target = D>,
         Fbrw: FnOnce() -> Rbrw,
         Rbrw: Deref<Target = D>,
     {
+        if self.auto_add_crates && self.editing.is_none() {
+            for name in self.unknown_crates(&input) {
+                if let Ok(cr) = CrateType::parse_str(&format!("extern crate {};", name)) {
+                    writer.write_line(&format!("adding crate dependency `{}`", name));
+                    input.crates.push(cr);
+                }
+            }
+        }
+
         let (nitems, ncrates) = (input.items.len(), input.crates.len());
 
         let has_stmts = !input.stmts.is_empty();
@@ -239,7 +709,14 @@ impl<D> ReplData<D> {
 
         let mut undo = true;
 
-        let (stmt_idx, item_idx, crate_idx) = if let Some(ei) = self.editing.take() {
+        // transient inputs do not consume an edit
+        let editing = if transient {
+            None
+        } else {
+            self.editing.take()
+        };
+
+        let (stmt_idx, item_idx, crate_idx) = if let Some(ei) = editing {
             let src = self.get_current_file_mut(); // remove at the index
                                                    // then insert, so
                                                    // acts like replace
@@ -280,9 +757,16 @@ impl<D> ReplData<D> {
             (lstmts, litem, lcrates)
         };
 
+        // mutating inputs are normally discarded, keep a copy if persisting
+        let persist = if self.linking.mutable && self.mut_block.persist && !transient {
+            Some(input.clone())
+        } else {
+            None
+        };
+
         self.insert_input(input, stmt_idx, item_idx, crate_idx);
 
-        let maybe_pop_input = |repl_data: &mut ReplData<D>| {
+        let maybe_pop_input = |repl_data: &mut ReplData<D, S>| {
             if undo {
                 let src = repl_data.get_current_file_mut();
 
@@ -309,17 +793,19 @@ impl<D> ReplData<D> {
         );
         if let Err(e) = res {
             maybe_pop_input(self); // failed so don't save
-            return EvalOutput::Print(Cow::Owned(format!(
+            return Err(Cow::Owned(format!(
                 "failed to build compile directory: {}",
                 e
             )));
         }
 
         // compile
+        let start = Instant::now();
         let lib_file = compile::compile(&self.compilation_dir, &self.linking, |line| {
             writer.erase_last_line();
             writer.write_str(line);
         });
+        self.eval_timings = (start.elapsed(), Duration::default());
 
         writer.erase_last_line();
 
@@ -327,12 +813,15 @@ impl<D> ReplData<D> {
             Ok(f) => f,
             Err(e) => {
                 maybe_pop_input(self); // failed so don't save
-                return EvalOutput::Print(Cow::Owned(format!("{}", e)));
+                return Err(Cow::Owned(format!("{}", e)));
             }
         };
 
         if has_stmts {
+            let mut restored = false;
+
             // execute
+            let start = Instant::now();
             let exec_res = {
                 // once compilation succeeds and we are going to evaluate it (which libloads) we
                 // first rename the files to avoid locking for the next compilation that might
@@ -345,15 +834,26 @@ impl<D> ReplData<D> {
                 if self.linking.mutable {
                     let mut r = obtain_mut_data();
                     let app_data: &mut D = r.borrow_mut();
-                    compile::exec(&lib_file, &fn_name, app_data)
+                    let snapshot = if self.mut_block.transactional {
+                        self.snapshot_app_data(app_data)
+                    } else {
+                        None
+                    };
+                    let res = compile::exec(&lib_file, &fn_name, &mut *app_data);
+                    if let (Err(_), Some(snapshot)) = (&res, snapshot) {
+                        *app_data = snapshot; // failed or panicked, roll back the changes
+                        restored = true;
+                    }
+                    res
                 } else {
                     let r = obtain_brw_data();
                     let app_data: &D = r.borrow();
                     compile::exec(&lib_file, &fn_name, app_data)
                 }
             };
+            self.eval_timings.1 = start.elapsed();
             match exec_res {
-                Ok((kserd, lib)) => {
+                Ok((kserd, type_name, lib)) => {
                     // store vec, maybe
                     add_to_limit_vec(
                         &mut self.loadedlibs,
@@ -361,25 +861,102 @@ impl<D> ReplData<D> {
                         self.loaded_libs_size_limit,
                     );
 
-                    if self.linking.mutable {
+                    if transient {
+                        maybe_pop_input(self); // don't save transient inputs
+                        Ok(EvalOutput::Data(kserd, type_name))
+                    } else if self.linking.mutable {
                         maybe_pop_input(self); // don't save mutating inputs
-                        EvalOutput::Print(Cow::Owned(format!("finished mutating block: {}", kserd)))
+                        if let Some(input) = persist {
+                            let path = self.current_mod.clone();
+                            self.persisted_mut_blocks
+                                .push((path, input, self.mut_block));
+                        }
+                        Ok(EvalOutput::Print(Cow::Owned(format!(
+                            "finished mutating block: {}",
+                            kserd
+                        ))))
                     // don't print as `out#`
                     } else {
-                        EvalOutput::Data(kserd)
+                        Ok(EvalOutput::Data(kserd, type_name))
                     }
                 }
+                Err(e) if restored => {
+                    maybe_pop_input(self); // failed so don't save
+                    Err(Cow::Owned(format!(
+                        "{}\napp data restored to before the mut block",
+                        e
+                    )))
+                }
                 Err(e) => {
                     maybe_pop_input(self); // failed so don't save
-                    EvalOutput::Print(Cow::Borrowed(e))
+                    Err(e)
                 }
             }
         } else {
             // this will keep inputs, might not be preferrable to do so in mutating state?
-            EvalOutput::Print(Cow::Borrowed("")) // do not execute if no extra statements have been added
+            Ok(EvalOutput::Print(Cow::Borrowed(""))) // do not execute if no extra statements have been added
         }
     }
 
+    /// Compiles the REPL code without evaluating it, to check a change to the configuration.
+    pub(crate) fn trial_compile(&self) -> Result<(), String> {
+        compile::build_compile_dir(
+            &self.compilation_dir,
+            &self.mods_map,
+            &self.linking,
+            &self.static_files,
+        )
+        .map_err(|e| format!("failed to build compile directory: {}", e))?;
+
+        compile::compile(&self.compilation_dir, &self.linking, |_| ())
+            .map(|_| ())
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// Crates referenced by paths in the input which the REPL does not know of.
+    fn unknown_crates(&self, input: &Input) -> Vec<String> {
+        let crates = self
+            .mods_map
+            .values()
+            .flat_map(|src| src.crates.iter())
+            .chain(self.static_files.iter().flat_map(|f| f.crates.iter()));
+
+        let mut known: Vec<&str> = BUILTIN_CRATES.to_vec();
+        for cr in crates {
+            known.push(&cr.name);
+            known.extend(cr.alias.as_deref());
+        }
+        for ext in &self.linking.external_libs {
+            known.push(ext.lib_name());
+            known.extend(ext.alias());
+        }
+        known.extend(
+            self.static_files
+                .iter()
+                .filter_map(|f| code::static_file_mod_name(&f.path)),
+        );
+        known.extend(
+            self.mods_map
+                .keys()
+                .flat_map(|p| p.iter())
+                .filter_map(|x| x.to_str()),
+        );
+
+        let items = self
+            .current_src()
+            .items
+            .iter()
+            .map(|x| x.0.as_str())
+            .chain(std::iter::once(
+                self.linking.persistent_module_code.as_str(),
+            ));
+
+        crate::input::crate_roots(input, items)
+            .into_iter()
+            .filter(|x| !known.contains(&x.as_str()))
+            .collect()
+    }
+
     fn insert_input(&mut self, input: Input, stmt_idx: usize, item_idx: usize, crate_idx: usize) {
         let Input {
             items,
@@ -410,6 +987,47 @@ impl<D> ReplData<D> {
     }
 }
 
+/// Crates which are always available, these are not added as dependencies.
+const BUILTIN_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test", "kserd"];
+
+/// A duration as a readable string, such as `1.5ms`.
+fn duration_kserd(duration: Duration) -> Kserd<'static> {
+    Kserd::new_string(format!("{:?}", duration))
+}
+
+/// Parses an expression for a transient evaluation, which can not contain items or crates.
+fn parse_transient_expr(expr: &str, cmd: &str) -> Result<Input, Cow<'static, str>> {
+    match crate::input::parse_program(expr) {
+        InputResult::Program(input)
+            if input.items.is_empty() && input.crates.is_empty() && !input.stmts.is_empty() =>
+        {
+            Ok(input)
+        }
+        InputResult::InputError(e) => Err(Cow::Owned(e)),
+        _ => Err(Cow::Owned(format!("{} expects an expression", cmd))),
+    }
+}
+
+/// A short description of the input, used when reporting a failure.
+fn describe_input(input: &Input) -> String {
+    if input.stmts.is_empty() {
+        input
+            .items
+            .iter()
+            .map(|x| x.0.as_str())
+            .chain(input.crates.iter().map(|x| x.src_line.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        input
+            .stmts
+            .iter()
+            .map(|x| x.expr.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
 fn add_to_limit_vec<T>(store: &mut VecDeque<T>, item: T, limit: usize) {
     match (limit, store.len()) {
         (0, 0) => (),             // do nothing, lib will drop after this
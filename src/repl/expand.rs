@@ -0,0 +1,158 @@
+use super::*;
+use crate::{code, compile};
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{Block, Pat, Stmt};
+
+impl<D, S> ReplData<D, S> {
+    /// Macro expand an input in the current module, returning its expanded code.
+    ///
+    /// `index` is the input number, such as `2` for `out2`, and defaults to the last input. The
+    /// whole session is expanded by the compiler, with the statements of the input marked so they
+    /// can be cut out of the expanded code. This requires a nightly compiler.
+    pub fn expand_input(&self, index: Option<usize>) -> Result<String, ExpandError> {
+        let src = self.current_src();
+        let count = src.stmts.len();
+        let index = match index {
+            Some(i) if i < count => i,
+            Some(i) => return Err(ExpandError::NoInput(i)),
+            None => count.checked_sub(1).ok_or(ExpandError::NoInput(0))?,
+        };
+
+        compile::build_compile_dir(
+            &self.compilation_dir,
+            &self.mods_map,
+            &self.linking,
+            &self.static_files,
+        )
+        .map_err(ExpandError::Io)?;
+
+        // rewrite the library with the input's statements between markers
+        let (mut code, map) =
+            code::construct_source_code(&self.mods_map, &self.linking, &self.static_files);
+        let range = map
+            .get(self.current_mod())
+            .and_then(|rng| code::input_range(src, index, rng))
+            .ok_or(ExpandError::NotFound(index))?;
+        code.insert_str(range.end, &marker(END_MARKER));
+        code.insert_str(range.start, &marker(START_MARKER));
+        fs::write(self.compilation_dir.join("src/lib.rs"), code).map_err(ExpandError::Io)?;
+
+        let expanded =
+            compile::expand(&self.compilation_dir, &self.linking).map_err(ExpandError::Compile)?;
+
+        find_input(&expanded)?
+            .map(fmt)
+            .ok_or(ExpandError::NotFound(index))
+    }
+}
+
+/// The binding which marks the start of the input's statements.
+const START_MARKER: &str = "__papyrus_expand_start";
+/// The binding which marks the end of the input's statements.
+const END_MARKER: &str = "__papyrus_expand_end";
+
+fn marker(name: &str) -> String {
+    format!("let {} = ();\n", name)
+}
+
+/// Finds the statements between the markers in the expanded code.
+fn find_input(expanded: &str) -> Result<Option<String>, ExpandError> {
+    let file = syn::parse_file(expanded).map_err(|e| ExpandError::Parse(e.to_string()))?;
+
+    let mut finder = FindInput(None);
+    finder.visit_file(&file);
+
+    Ok(finder.0)
+}
+
+struct FindInput(Option<String>);
+
+impl<'ast> Visit<'ast> for FindInput {
+    fn visit_block(&mut self, i: &'ast Block) {
+        if self.0.is_some() {
+            return;
+        }
+
+        let stmts = &i.stmts;
+        let start = stmts.iter().position(|x| is_marker(x, START_MARKER));
+        let end = stmts.iter().position(|x| is_marker(x, END_MARKER));
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => {
+                let code = stmts[start + 1..end]
+                    .iter()
+                    .map(|x| x.to_token_stream().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.0 = Some(code);
+            }
+            _ => visit::visit_block(self, i), // async and fallible statements are nested
+        }
+    }
+}
+
+/// The statement is the `let` binding of the marker.
+fn is_marker(stmt: &Stmt, marker: &str) -> bool {
+    match stmt {
+        Stmt::Local(local) => matches!(&local.pat, Pat::Ident(p) if p.ident == marker),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "format")]
+fn fmt(s: String) -> String {
+    crate::fmt::format(&s).unwrap_or(s)
+}
+
+#[cfg(not(feature = "format"))]
+fn fmt(s: String) -> String {
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_input_test() {
+        let expanded = r#"#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2018::*;
+#[no_mangle]
+pub extern "C" fn _lib_intern_eval() -> (kserd::Kserd<'static>, String) {
+    let out0 = 1;
+    let __papyrus_expand_start = ();
+    let out1 = 2;
+    { ::std::io::_print(format_args!("{0}\n", out1)); };
+    let out2 = out1;
+    let __papyrus_expand_end = ();
+    fn type_name_of<T>(_: &T) -> String { std::any::type_name::<T>().to_string() }
+    let out_type = type_name_of(&out2);
+    (kserd::ToKserd::into_kserd(out2).unwrap().into_owned(), out_type)
+}
+"#;
+
+        let s = find_input(expanded).unwrap().unwrap();
+        assert!(s.starts_with("let out1 = 2 ;"));
+        assert!(s.ends_with("let out2 = out1 ;"));
+
+        // nested in a fallible closure
+        let expanded = r#"pub extern "C" fn _foo_intern_eval() -> (kserd::Kserd<'static>, String) {
+    match (|| {
+        let __papyrus_expand_start = ();
+        let out0 = "1".parse::<i32>()?;
+        let __papyrus_expand_end = ();
+        Ok::<_, Box<dyn std::error::Error>>((kserd::ToKserd::into_kserd(out0).unwrap().into_owned(), String::new()))
+    })() {
+        Ok(x) => x,
+        Err(e) => (kserd::Kserd::new_string(e.to_string()), String::from("<error>")),
+    }
+}
+"#;
+        let s = find_input(expanded).unwrap().unwrap();
+        assert_eq!(s, "let out0 = \"1\" . parse :: < i32 > () ? ;");
+
+        assert_eq!(find_input("fn a() { let out0 = 1; }").unwrap(), None);
+    }
+}
@@ -1,15 +1,19 @@
 //! The REPL API.
 //!
 //! The REPL uses a state machine to control what methods can be applied to it.
+mod alias;
 mod any_state;
 mod data;
 mod eval;
+mod expand;
 mod print;
 mod read;
+mod session;
 
 use crate::{
-    cmds::CommandResult,
+    cmds::{CancelHandle, CommandResult},
     code::{ModsMap, StaticFile, StaticFiles},
+    complete::cmdr::{ArgComplete, ArgCompleterFn},
     input::InputResult,
     linking::{self, LinkingConfiguration},
     output::{self, Output},
@@ -20,19 +24,22 @@ use crossbeam_channel::Receiver;
 use kserd::Kserd;
 use std::{
     borrow::Cow,
-    collections::VecDeque,
-    fmt, fs, io,
+    collections::{BTreeMap, VecDeque},
+    error, fmt, fs, io,
     marker::PhantomData,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 /// The repl structure. Stored as a state machine.
 /// See the [module level documentation] for more information.
 ///
 /// A repl has different available methods depending on its state.
-pub struct Repl<S, Data> {
+///
+/// `Sig` is the payload type of custom signals sent by commands, see [`Signal::Custom`].
+pub struct Repl<S, Data, Sig = ()> {
     /// The inner repl configuration data.
-    pub data: ReplData<Data>,
+    pub data: ReplData<Data, Sig>,
 
     state: S,
 
@@ -42,10 +49,22 @@ pub struct Repl<S, Data> {
     data_mrker: PhantomData<Data>,
 }
 
+/// Builds the root of the command tree.
+type CmdtreeBuilderFn<D, S> = Box<dyn Fn() -> Builder<CommandResult<D, S>> + Send>;
+
 /// The inner configuration data of the repl.
-pub struct ReplData<Data> {
+pub struct ReplData<Data, S = ()> {
     /// The REPL commands as a `cmdtree::Commander`.
-    pub cmdtree: Commander<CommandResult<Data>>,
+    pub cmdtree: Commander<CommandResult<Data, S>>,
+    /// Builds the root of the command tree, the commander is rebuilt when aliases change.
+    /// `None` if the command tree was set with a single `Builder`, which can not be rebuilt.
+    pub(crate) cmdtree_builder: Option<CmdtreeBuilderFn<Data, S>>,
+    /// Command aliases, mapping the name to the steps it expands to.
+    pub(crate) aliases: BTreeMap<String, String>,
+    /// The configuration file aliases are saved to.
+    alias_config: Option<PathBuf>,
+    /// Argument completers of command actions, keyed by the qualified path of the action.
+    pub(crate) arg_completers: BTreeMap<String, Box<ArgCompleterFn>>,
 
     /// The modules map of relative paths.
     pub(crate) mods_map: ModsMap,
@@ -56,6 +75,14 @@ pub struct ReplData<Data> {
     pub prompt_colour: Color,
     /// The colour of the out component. ie `[out0]`.
     pub out_colour: Color,
+    /// Print the type of the result alongside the data. ie `[out0]: i32 = 4`.
+    /// Defaults to `true`.
+    pub show_result_type: bool,
+    /// Add crates referenced by `use` and paths as dependencies, if they are not known.
+    /// For example `use regex::Regex;` adds the `regex` crate as if `extern crate regex;` was
+    /// entered. A crate is added without confirmation, so this is opt-in.
+    /// Defaults to `false`.
+    pub auto_add_crates: bool,
 
     /// The directory for which compilation is done within.
     /// Defaults to `$HOME/.papyrus/`.
@@ -82,8 +109,24 @@ pub struct ReplData<Data> {
     /// to avoid repeating the contents.
     pub editing_src: Option<String>,
 
+    /// The options of the current mutable block.
+    pub(crate) mut_block: MutBlock,
+    /// Mutable blocks kept for replaying, with the module they were evaluated in.
+    pub(crate) persisted_mut_blocks: Vec<(PathBuf, crate::code::Input, MutBlock)>,
+    /// Snapshots the app data for transactional mutable blocks. Only set if `Data: Clone`.
+    clone_app_data: Option<fn(&Data) -> Data>,
+    /// The time taken to compile and to execute the last evaluation, as `(compile, exec)`.
+    pub(crate) eval_timings: (Duration, Duration),
+    /// Transforms applied in order to code input before it is parsed.
+    input_transforms: Vec<InputTransform>,
+
     /// Store of static files written to disk and to be included in REPL cycle.
     static_files: StaticFiles,
+    /// Reload the static files which changed on disk before each evaluation.
+    /// Defaults to `false`.
+    pub watch_static_files: bool,
+    /// Cancels a running task command.
+    pub(crate) cancel: CancelHandle,
 
     /// Stored loaded libraries of the papyrus mem code.
     loadedlibs: VecDeque<Box<libloading::Library>>,
@@ -99,6 +142,8 @@ pub struct ReplData<Data> {
     /// The default is to keep the size limit at zero, thus ensuring no libraries are kept in
     /// memory. This is recommended unless issues are arising from esoteric use cases.
     pub loaded_libs_size_limit: usize,
+    /// The number of inputs kept in the history when running the REPL. Defaults to 100.
+    pub history_size: usize,
 }
 
 /// Repl read state.
@@ -115,8 +160,8 @@ pub struct Evaluate {
 }
 
 /// Repl evaluating state. This can be constructed via a `eval_async` call.
-pub struct Evaluating<D> {
-    jh: Receiver<EvalResult<D>>,
+pub struct Evaluating<D, S = ()> {
+    jh: Receiver<EvalResult<D, S>>,
 }
 
 /// Repl print state.
@@ -130,23 +175,27 @@ pub struct Print {
 #[derive(Debug)]
 enum EvalOutput {
     /// If there is data, then it should be prefixed with `[out#]`.
-    Data(Kserd<'static>),
+    /// Includes the type name of the data.
+    Data(Kserd<'static>, String),
+    /// A value returned by a command, prefixed with `[:cmd]`.
+    /// Includes the command line which was invoked.
+    Value(Kserd<'static>, String),
     Print(Cow<'static, str>),
 }
 
 /// Represents an evaluating result. Signal should be checked and handled.
-pub struct EvalResult<D> {
+pub struct EvalResult<D, S = ()> {
     /// The repl, in print ready state.
-    pub repl: Repl<Print, D>,
+    pub repl: Repl<Print, D, S>,
     /// The signal, if any.
-    pub signal: Signal,
+    pub signal: Signal<S>,
 }
 
 /// Return signals from evaluating.
 /// Sometimes there are extra signals that result from evaluating,
 /// such as the signal to exit the repl. These signals are enumerated here.
 #[derive(Debug, PartialEq)]
-pub enum Signal {
+pub enum Signal<S = ()> {
     /// No signal was sent.
     None,
     /// A signal to exit the repl has been sent.
@@ -160,16 +209,141 @@ pub enum Signal {
     ///
     /// [`EditReplace`]: super::cmds::CommandResult
     ReEvaluate(String),
+    /// An application defined signal, sent by a [`CustomSignal`] command.
+    ///
+    /// [`CustomSignal`]: super::cmds::CommandResult
+    Custom(S),
 }
 
 /// Result of [`read`]ing the current input buffer.
 ///
 /// [`read`]: Repl::read
-pub enum ReadResult<D> {
+pub enum ReadResult<D, S = ()> {
     /// The repl is still in a read state.
-    Read(Repl<Read, D>),
+    Read(Repl<Read, D, S>),
     /// The repl is in an eval state.
-    Eval(Repl<Evaluate, D>),
+    Eval(Repl<Evaluate, D, S>),
+}
+
+/// Errors from loading a source file as REPL input.
+#[derive(Debug)]
+pub enum LoadError {
+    /// An io error occurred.
+    Io(io::Error),
+    /// The file could not be parsed.
+    Parse(String),
+    /// An input failed to compile or evaluate. Holds the failing input and the error message.
+    Eval(String, String),
+}
+
+impl error::Error for LoadError {}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "an io error occurred: {}", e),
+            LoadError::Parse(e) => write!(f, "failed to parse file: {}", e),
+            LoadError::Eval(input, e) => write!(f, "failed evaluating `{}`: {}", input, e),
+        }
+    }
+}
+
+/// Errors from saving or loading a session.
+#[derive(Debug)]
+pub enum SessionError {
+    /// An io error occurred.
+    Io(io::Error),
+    /// The session could not be converted into `kserd`.
+    Serialise(kserd::ToKserdErr),
+    /// The session file could not be parsed as `kserd`.
+    Parse(String),
+    /// The session file version is not supported.
+    Version(u128),
+    /// The session file is not in the expected format.
+    Format(&'static str),
+    /// A static file could not be added.
+    StaticFile(crate::code::AddingStaticFileError),
+}
+
+impl error::Error for SessionError {}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "an io error occurred: {}", e),
+            SessionError::Serialise(e) => write!(f, "failed to serialise session: {}", e),
+            SessionError::Parse(e) => write!(f, "failed to parse session file: {}", e),
+            SessionError::Version(v) => write!(f, "unsupported session version: {}", v),
+            SessionError::Format(e) => write!(f, "invalid session file: {}", e),
+            SessionError::StaticFile(e) => write!(f, "failed to add static file: {}", e),
+        }
+    }
+}
+
+/// Errors from macro expanding an input.
+#[derive(Debug)]
+pub enum ExpandError {
+    /// There is no input with the number in the current module.
+    NoInput(usize),
+    /// An io error occurred building the compilation directory.
+    Io(io::Error),
+    /// The compiler failed to expand the code.
+    Compile(crate::compile::CompilationError),
+    /// The expanded code could not be parsed.
+    Parse(String),
+    /// The input could not be found in the expanded code.
+    NotFound(usize),
+}
+
+impl error::Error for ExpandError {}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpandError::NoInput(i) => write!(f, "there is no input `out{}`", i),
+            ExpandError::Io(e) => write!(f, "failed to build compile directory: {}", e),
+            ExpandError::Compile(e) => write!(f, "failed to expand: {}", e),
+            ExpandError::Parse(e) => write!(f, "failed to parse expanded code: {}", e),
+            ExpandError::NotFound(i) => write!(f, "could not find `out{}` in expanded code", i),
+        }
+    }
+}
+
+/// Errors from defining or expanding a command alias.
+#[derive(Debug)]
+pub enum AliasError {
+    /// The name is not a single word of alphanumeric characters, `-`, or `_`.
+    InvalidName(String),
+    /// The name is already a command.
+    Conflict(String),
+    /// There is no alias with the name.
+    Unknown(String),
+    /// The alias expands to itself.
+    Recursive(String),
+    /// The configuration file could not be read or written.
+    Config(crate::config::ConfigError),
+    /// The command tree was set with
+    /// [`with_cmdtree_builder`](ReplData::with_cmdtree_builder) and can not be rebuilt.
+    NotRebuildable,
+}
+
+impl error::Error for AliasError {}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasError::InvalidName(name) => write!(f, "`{}` is not a valid alias name", name),
+            AliasError::Conflict(name) => write!(f, "`{}` is already a command", name),
+            AliasError::Unknown(name) => write!(f, "there is no alias `{}`", name),
+            AliasError::Recursive(name) => write!(f, "alias `{}` expands to itself", name),
+            AliasError::Config(e) => write!(f, "failed to access alias config: {}", e),
+            AliasError::NotRebuildable => write!(
+                f,
+                "aliases can not be changed as the command tree can not be rebuilt, \
+                 use `ReplData::with_cmdtree_builder_fn`"
+            ),
+        }
+    }
 }
 
 /// The index of the statement group, item, or crate being edited.
@@ -192,6 +366,33 @@ pub enum Editing {
     Crate,
 }
 
+/// A transform of code input, applied before the input is parsed.
+///
+/// Use [`ReplData::with_input_transform`] to register a transform.
+pub type InputTransform = Box<dyn Fn(&str) -> Transformed + Send>;
+
+/// The outcome of an [`InputTransform`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transformed {
+    /// The input is passed on as is.
+    Unchanged,
+    /// The input is replaced with the rewritten input.
+    Rewritten(String),
+    /// The input is rejected with the reason, and is not evaluated.
+    Rejected(String),
+}
+
+/// Options of a mutable block, set when beginning the block with `:mut`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MutBlock {
+    /// Keep the input once evaluated, such that it can be replayed with `:mut replay`.
+    pub persist: bool,
+    /// Snapshot the app data before evaluating and restore it if the block fails or panics.
+    ///
+    /// Requires [`ReplData::with_transactional_mut`].
+    pub transactional: bool,
+}
+
 /// `$HOME/.papyrus`
 fn default_compile_dir() -> PathBuf {
     dirs::home_dir().unwrap_or_default().join(".papyrus/")
@@ -2,20 +2,25 @@ use super::*;
 use ::kserd::fmt::FormattingConfig;
 
 /// > **These methods are available when the REPL is in the [`Print`] state.**
-impl<D> Repl<Print, D> {
+impl<D, S> Repl<Print, D, S> {
     /// Prints the result if successful as `[out#]` or the failure message if any.
     /// Uses the default formatter for the `Kserd` data.
-    pub fn print(self) -> (Repl<Read, D>, Option<(usize, Kserd<'static>)>) {
+    #[allow(clippy::type_complexity)]
+    pub fn print(self) -> (Repl<Read, D, S>, Option<(Option<usize>, Kserd<'static>)>) {
         self.print_with_formatting(FormattingConfig::default())
     }
 
     /// Prints the result if successful as `[out#]` or the failure message if any.
     /// Uses the given formatting configuration for the `Kserd` data.
     /// The return is (<repl in read state>, <maybe <stmt index, data>>)
+    ///
+    /// A value returned by a command is printed as `[:cmd]` and is returned without an index, as
+    /// it does not belong to a statement.
+    #[allow(clippy::type_complexity)]
     pub fn print_with_formatting(
         self,
         config: FormattingConfig,
-    ) -> (Repl<Read, D>, Option<(usize, Kserd<'static>)>) {
+    ) -> (Repl<Read, D, S>, Option<(Option<usize>, Kserd<'static>)>) {
         let Repl {
             state,
             data,
@@ -30,21 +35,42 @@ impl<D> Repl<Print, D> {
         let mut kserd = None;
 
         match data {
-            EvalOutput::Data(k) => {
+            EvalOutput::Data(k, type_name) => {
                 let num = repl_data.current_src().stmts.len().saturating_sub(1);
 
                 let out_stmt = format!("[out{}]", num);
 
+                let type_name = if repl_data.show_result_type {
+                    format!("{} = ", type_name)
+                } else {
+                    String::new()
+                };
+
                 let line = format!(
-                    "{} {}: {}",
+                    "{} {}: {}{}",
                     repl_data.cmdtree.path().color(repl_data.prompt_colour),
                     out_stmt.color(repl_data.out_colour),
+                    type_name,
+                    k.as_str_with_config(config)
+                );
+
+                output.write_line(&line);
+
+                kserd = Some((Some(num), k));
+            }
+            EvalOutput::Value(k, cmd) => {
+                let out_cmd = format!("[{}{}]", crate::CMD_PREFIX, cmd);
+
+                let line = format!(
+                    "{} {}: {}",
+                    repl_data.cmdtree.path().color(repl_data.prompt_colour),
+                    out_cmd.color(repl_data.out_colour),
                     k.as_str_with_config(config)
                 );
 
                 output.write_line(&line);
 
-                kserd = Some((num, k));
+                kserd = Some((None, k));
             }
             EvalOutput::Print(print) => {
                 if print.len() > 0 {
@@ -69,7 +95,7 @@ impl<D> Repl<Print, D> {
     }
 }
 
-fn prepare_read<D>(repl: &mut Repl<Read, D>) {
+fn prepare_read<D, S>(repl: &mut Repl<Read, D, S>) {
     repl.draw_prompt();
 
     let editing_src = repl.data.editing.and_then(|ei| {
@@ -1,6 +1,6 @@
 use super::*;
 
-impl<D> Default for Repl<Read, D> {
+impl<D, S> Default for Repl<Read, D, S> {
     fn default() -> Self {
         let data = ReplData::default();
 
@@ -21,7 +21,7 @@ impl<D> Default for Repl<Read, D> {
 }
 
 /// > **These methods are available when the REPL is in the [`Read`] state.**
-impl<D> Repl<Read, D> {
+impl<D, S> Repl<Read, D, S> {
     /// Overwrite the current line in the input buffer.
     ///
     /// A line is considered if more input is required, the previous input stacked.
@@ -46,14 +46,31 @@ impl<D> Repl<Read, D> {
 
     /// Read the current contents of the input buffer.
     /// This may move the repl into an evaluating state.
-    pub fn read(mut self) -> ReadResult<D> {
+    pub fn read(mut self) -> ReadResult<D, S> {
         let treat_as_cmd = !self.data.cmdtree.at_root();
 
-        let result = crate::input::determine_result(
-            self.state.output.input_buffer(),
-            self.state.output.input_buf_line(),
-            treat_as_cmd,
-        );
+        let input = self.state.output.input_buffer();
+        let line = self.state.output.input_buf_line();
+
+        // transforms apply to complete code input
+        let is_code = !treat_as_cmd && !line.starts_with(crate::CMD_PREFIX);
+        let transformed = if is_code && !crate::input::needs_more(input) {
+            self.data.transform_input(input)
+        } else {
+            Ok(None)
+        };
+
+        let (result, rewritten) = match transformed {
+            Ok(None) => (
+                crate::input::determine_result(input, line, treat_as_cmd),
+                None,
+            ),
+            Ok(Some(s)) => (crate::input::determine_result(&s, &s, false), Some(s)),
+            Err(reason) => (
+                InputResult::InputError(format!("input rejected: {}", reason)),
+                None,
+            ),
+        };
 
         // have to push after as can't take mutable brw and last line
         // if done before will not register cmds
@@ -65,9 +82,12 @@ impl<D> Repl<Read, D> {
             ReadResult::Read(self)
         } else {
             self.more = false;
-            ReadResult::Eval(self.move_state(|s| Evaluate {
-                output: s.output.into_write(),
-                result,
+            ReadResult::Eval(self.move_state(|s| {
+                let mut output = s.output.into_write();
+                if let Some(s) = rewritten {
+                    output.write_line(&format!("input rewritten: {}", s));
+                }
+                Evaluate { output, result }
             }))
         }
     }
@@ -94,9 +114,9 @@ impl<D> Repl<Read, D> {
     }
 }
 
-impl<D> ReadResult<D> {
+impl<D, S> ReadResult<D, S> {
     #[cfg(test)]
-    pub fn unwrap_read(self) -> Repl<Read, D> {
+    pub fn unwrap_read(self) -> Repl<Read, D, S> {
         match self {
             ReadResult::Read(read) => read,
             ReadResult::Eval(_) => panic!("unwrap_read ReadResult invoked on Eval variant."),
@@ -106,8 +126,59 @@ impl<D> ReadResult<D> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate as papyrus;
 
+    fn read_eval(mut repl: Repl<Read, ()>, input: &str) -> Repl<Evaluate, ()> {
+        repl.line_input(input);
+        match repl.read() {
+            ReadResult::Eval(repl) => repl,
+            ReadResult::Read(_) => panic!("expecting eval state"),
+        }
+    }
+
+    #[test]
+    fn test_input_transforms() {
+        let mut repl = repl!();
+        repl.data
+            .with_input_transform(|input| match input.strip_prefix('@') {
+                Some(user) => Transformed::Rewritten(format!("get_user({:?})", user)),
+                None => Transformed::Unchanged,
+            })
+            .with_input_transform(|input| {
+                if input.contains("secret") {
+                    Transformed::Rejected("no secrets".to_string())
+                } else {
+                    Transformed::Unchanged
+                }
+            });
+
+        // transforms see the output of previous transforms
+        let repl = read_eval(repl, "@secret");
+        assert_eq!(
+            repl.state.result,
+            InputResult::InputError("input rejected: no secrets".to_string())
+        );
+
+        // commands are not transformed
+        let repl = read_eval(repl.eval(&mut ()).repl.print().0, ":help secret");
+        assert_eq!(
+            repl.state.result,
+            InputResult::Command("help secret".to_string())
+        );
+
+        let repl = read_eval(repl.eval(&mut ()).repl.print().0, "@user");
+        assert!(repl
+            .state
+            .output
+            .buffer()
+            .ends_with("input rewritten: get_user(\"user\")\n"));
+        match &repl.state.result {
+            InputResult::Program(input) => assert_eq!(input.stmts[0].expr, "get_user(\"user\")"),
+            _ => panic!("expecting a program"),
+        }
+    }
+
     #[test]
     fn test_line_input() {
         let mut repl = repl!();
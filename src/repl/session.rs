@@ -0,0 +1,346 @@
+use super::*;
+use crate::{
+    code::{AddingStaticFileError, SourceCode},
+    linking::Extern,
+};
+use kserd::{ToKserd, Value};
+use std::collections::HashMap;
+
+/// The version of the session format. Bump when the format changes.
+const SESSION_VERSION: u8 = 2;
+
+impl<D, S> ReplData<D, S> {
+    /// Save the session to a file.
+    ///
+    /// A session consists of the modules and their source code, the current module, the static
+    /// files (including their contents), the externally linked libraries, the crate name mappings,
+    /// and the persistent module code. It is written in the `kserd` text format, tagged with a format version.
+    pub fn save_session<P: AsRef<Path>>(&self, path: P) -> Result<(), SessionError> {
+        let kserd = self.session_kserd()?;
+        fs::write(path, kserd.as_str()).map_err(SessionError::Io)
+    }
+
+    /// Load a session from a file, replacing the current session.
+    ///
+    /// The whole file is validated before any changes are made. Static files are rewritten into the
+    /// compilation directory and externally linked libraries must still exist on disk.
+    pub fn load_session<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SessionError> {
+        use SessionError::Format;
+
+        let s = fs::read_to_string(path).map_err(SessionError::Io)?;
+        let kserd = kserd::parse::parse(&s).map_err(|e| SessionError::Parse(format!("{:?}", e)))?;
+        let cntr = kserd.cntr().ok_or(Format("expecting a container"))?;
+
+        let version = cntr
+            .get("version")
+            .and_then(|x| x.uint())
+            .ok_or(Format("missing version"))?;
+        if version != u128::from(SESSION_VERSION) {
+            return Err(SessionError::Version(version));
+        }
+
+        let current_mod = cntr
+            .get_str("current_mod")
+            .map(PathBuf::from)
+            .ok_or(Format("missing current module"))?;
+
+        let mut mods_map = ModsMap::new();
+        for m in cntr.get_seq("mods").ok_or(Format("missing modules"))? {
+            let m = m.cntr().ok_or(Format("expecting module container"))?;
+            let path = m.get_str("path").ok_or(Format("missing module path"))?;
+            let src = m
+                .get("src")
+                .and_then(SourceCode::from_kserd)
+                .ok_or(Format("invalid module source code"))?;
+            mods_map.insert(PathBuf::from(path), src);
+        }
+
+        if !mods_map.contains_key(Path::new("lib")) || !mods_map.contains_key(&current_mod) {
+            return Err(Format("missing `lib` or current module"));
+        }
+
+        // staged so that nothing changes if a static file is invalid
+        let mut static_files = Vec::new();
+        for sf in cntr
+            .get_seq("static_files")
+            .ok_or(Format("missing static files"))?
+        {
+            let sf = sf.cntr().ok_or(Format("expecting static file container"))?;
+            let file = sf
+                .get("file")
+                .and_then(StaticFile::from_kserd)
+                .ok_or(Format("invalid static file"))?;
+            let code = sf
+                .get_str("code")
+                .ok_or(Format("missing static file code"))?;
+            let (file, code) =
+                StaticFile::parse(file.path, code).map_err(SessionError::StaticFile)?;
+            static_files.push((file, code.to_string()));
+        }
+
+        let mut externs = Vec::new();
+        for e in cntr.get_seq("externs").ok_or(Format("missing externs"))? {
+            let e = e.cntr().ok_or(Format("expecting extern container"))?;
+            let path = e.get_str("path").ok_or(Format("missing extern path"))?;
+            let ext = match e.get_str("alias") {
+                Some(alias) => Extern::with_alias(path, alias),
+                None => Extern::new(path),
+            }
+            .map_err(SessionError::Io)?;
+            externs.push(ext);
+        }
+
+        let mut crate_names = HashMap::new();
+        for c in cntr
+            .get_seq("crate_names")
+            .ok_or(Format("missing crate names"))?
+        {
+            let c = c.cntr().ok_or(Format("expecting crate name container"))?;
+            let name = c.get_str("name").ok_or(Format("missing crate name"))?;
+            let package = c
+                .get_str("package")
+                .ok_or(Format("missing crate package name"))?;
+            crate_names.insert(name.to_string(), package.to_string());
+        }
+
+        let persistent_module_code = cntr
+            .get_str("persistent_module_code")
+            .ok_or(Format("missing persistent module code"))?
+            .to_string();
+
+        // validated, now apply, writing the static files first as they can fail
+        self.write_static_files(&static_files)
+            .map_err(|e| SessionError::StaticFile(AddingStaticFileError::Io(e)))?;
+
+        let static_files = static_files
+            .into_iter()
+            .map(|(file, _)| file)
+            .collect::<StaticFiles>();
+        for sf in self.static_files.difference(&static_files) {
+            fs::remove_file(self.static_file_name(&sf.path)).ok(); // swallow error
+        }
+        self.static_files = static_files;
+
+        self.mods_map = mods_map;
+        self.current_mod = current_mod;
+        self.editing = None;
+
+        self.linking.external_libs = externs.into_iter().collect();
+        self.linking.crate_names = crate_names;
+        self.linking.persistent_module_code = persistent_module_code;
+
+        Ok(())
+    }
+
+    /// Writes the code of the static files to disk. If a write fails, the files already written
+    /// are restored to their previous contents.
+    fn write_static_files(&self, files: &[(StaticFile, String)]) -> io::Result<()> {
+        let mut written = Vec::with_capacity(files.len());
+
+        for (file, code) in files {
+            let file_name = self.static_file_name(&file.path);
+            let prev = fs::read_to_string(&file_name).ok();
+            let parent = file_name.parent().expect("should exist");
+            let r = fs::create_dir_all(parent).and_then(|_| fs::write(&file_name, code));
+            written.push((file_name, prev));
+
+            if let Err(e) = r {
+                for (file_name, prev) in written {
+                    match prev {
+                        Some(prev) => fs::write(file_name, prev).ok(),
+                        None => fs::remove_file(file_name).ok(),
+                    };
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn session_kserd(&self) -> Result<Kserd<'static>, SessionError> {
+        let path_str = |p: &Path| Kserd::new_string(p.to_string_lossy().into_owned());
+
+        let mods = self
+            .mods_map
+            .iter()
+            .map(|(path, src)| {
+                Ok(Kserd::new_cntr(vec![
+                    ("path", path_str(path)),
+                    ("src", src.clone().into_kserd()?),
+                ])?)
+            })
+            .collect::<Result<Vec<_>, kserd::ToKserdErr>>()
+            .map_err(SessionError::Serialise)?;
+
+        let mut static_files = Vec::with_capacity(self.static_files.len());
+        for sf in &self.static_files {
+            // crates are stripped when written to disk, so rebuild the original code
+            let mut code = sf
+                .crates
+                .iter()
+                .map(|c| c.src_line.trim())
+                .collect::<Vec<_>>()
+                .join("\n");
+            code.push_str(
+                &fs::read_to_string(self.static_file_name(&sf.path)).map_err(SessionError::Io)?,
+            );
+
+            let file = StaticFile {
+                path: sf.path.clone(),
+                codehash: sf.codehash.clone(),
+                crates: sf.crates.clone(),
+            };
+
+            static_files.push(
+                Kserd::new_cntr(vec![
+                    ("file", file.into_kserd().map_err(SessionError::Serialise)?),
+                    ("code", Kserd::new_string(code)),
+                ])
+                .map_err(|e| SessionError::Serialise(e.into()))?,
+            );
+        }
+
+        let externs = self
+            .linking
+            .external_libs
+            .iter()
+            .map(|e| {
+                Ok(Kserd::new_cntr(vec![
+                    ("path", path_str(e.lib_path())),
+                    ("alias", e.alias().map(String::from).into_kserd()?),
+                ])?)
+            })
+            .collect::<Result<Vec<_>, kserd::ToKserdErr>>()
+            .map_err(SessionError::Serialise)?;
+
+        let mut crate_names = self.linking.crate_names.iter().collect::<Vec<_>>();
+        crate_names.sort_unstable();
+        let crate_names = crate_names
+            .into_iter()
+            .map(|(name, package)| {
+                Kserd::new_cntr(vec![
+                    ("name", Kserd::new_string(name.clone())),
+                    ("package", Kserd::new_string(package.clone())),
+                ])
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SessionError::Serialise(e.into()))?;
+
+        Kserd::with_id(
+            "PapyrusSession",
+            Value::new_cntr(vec![
+                ("version", Kserd::new_num(SESSION_VERSION)),
+                ("current_mod", path_str(&self.current_mod)),
+                ("mods", Kserd::new(Value::Seq(mods))),
+                ("static_files", Kserd::new(Value::Seq(static_files))),
+                ("externs", Kserd::new(Value::Seq(externs))),
+                ("crate_names", Kserd::new(Value::Seq(crate_names))),
+                (
+                    "persistent_module_code",
+                    Kserd::new_string(self.linking.persistent_module_code.clone()),
+                ),
+            ])
+            .map_err(|e| SessionError::Serialise(e.into()))?,
+        )
+        .map_err(|e| SessionError::Serialise(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::{Statement, StmtGrp};
+
+    #[test]
+    fn session_round_trip() {
+        let mut data: ReplData<()> = ReplData::default();
+        data.with_compilation_dir("./target/session-test").unwrap();
+        data.add_static_file(
+            "foo.rs".into(),
+            "extern crate rand;\nextern crate regex;\npub fn foo() {}",
+        )
+        .unwrap();
+        data.persistent_module_code().push_str("use std::io;");
+        data.with_crate_name("third", "third-party");
+        data.mods_map
+            .get_mut(Path::new("lib"))
+            .unwrap()
+            .stmts
+            .push(StmtGrp(vec![
+                Statement {
+                    expr: "let s = \"a \\\"quoted\\\" str\"".to_string(),
+                    semi: true,
+                },
+                Statement {
+                    expr: "s".to_string(),
+                    semi: false,
+                },
+            ]));
+        crate::cmds::switch_module(&mut data, Path::new("a/b"));
+
+        let file = "./target/session-test/session.kserd";
+        data.save_session(file).unwrap();
+
+        let mut loaded: ReplData<()> = ReplData::default();
+        loaded
+            .with_compilation_dir("./target/session-test")
+            .unwrap();
+        loaded.load_session(file).unwrap();
+
+        assert_eq!(loaded.current_mod(), Path::new("a/b"));
+        assert_eq!(loaded.mods_map().len(), 3);
+        assert_eq!(
+            loaded.mods_map()[Path::new("lib")].stmts[0].src_line(),
+            data.mods_map()[Path::new("lib")].stmts[0].src_line()
+        );
+        assert_eq!(loaded.linking().persistent_module_code, "use std::io;");
+        assert_eq!(
+            loaded
+                .linking()
+                .crate_names
+                .get("third")
+                .map(String::as_str),
+            Some("third-party")
+        );
+        let sf = loaded.static_files().iter().next().unwrap();
+        assert_eq!(sf.path, Path::new("foo.rs"));
+        assert_eq!(sf.crates.len(), 2);
+        // the code is rebuilt exactly
+        assert_eq!(
+            sf.codehash,
+            data.static_files().iter().next().unwrap().codehash
+        );
+    }
+
+    #[test]
+    fn failed_load_makes_no_changes() {
+        let dir = "./target/session-test-failed";
+        let mut data: ReplData<()> = ReplData::default();
+        data.with_compilation_dir(dir).unwrap();
+        data.add_static_file("foo.rs".into(), "pub fn foo() {}")
+            .unwrap();
+        crate::cmds::switch_module(&mut data, Path::new("a"));
+
+        let file = "./target/session-test-failed/session.kserd";
+        data.save_session(file).unwrap();
+        let s = fs::read_to_string(file)
+            .unwrap()
+            .replace("foo.rs", "not-valid.rs");
+        fs::write(file, s).unwrap();
+
+        let mut loaded: ReplData<()> = ReplData::default();
+        loaded.with_compilation_dir(dir).unwrap();
+        loaded
+            .add_static_file("bar.rs".into(), "pub fn bar() {}")
+            .unwrap();
+
+        assert!(matches!(
+            loaded.load_session(file),
+            Err(SessionError::StaticFile(_))
+        ));
+        assert_eq!(loaded.current_mod(), Path::new("lib"));
+        assert_eq!(loaded.static_files().len(), 1);
+        assert!(loaded.static_file_name(Path::new("bar.rs")).exists());
+    }
+}
@@ -1,5 +1,7 @@
 use super::map_xterm_err;
+use crate::cmds::CancelHandle;
 use crate::output::OutputChange;
+use colored::*;
 use crossbeam_channel::{unbounded, Receiver};
 use crossterm as xterm;
 use std::{
@@ -24,21 +26,23 @@ const TAB_WIDTH: usize = 8;
 pub struct Screen(pub(super) Receiver<Event>);
 
 impl Screen {
-    pub fn new() -> io::Result<Self> {
+    /// Buffers the terminal events. `Ctrl+C` cancels a running task rather than being buffered.
+    pub fn new(cancel: CancelHandle) -> io::Result<Self> {
+        const BREAK: Event = Key(KeyEvent {
+            modifiers: KeyModifiers::CONTROL,
+            code: Char('c'),
+        });
+
         let (tx, rx) = unbounded();
         std::thread::Builder::new()
             .name("terminal-event-buffer".into())
             .spawn(move || loop {
                 match xterm::event::poll(std::time::Duration::from_millis(5)) {
-                    Ok(true) => {
-                        if xterm::event::read()
-                            .ok()
-                            .and_then(|ev| tx.send(ev).ok())
-                            .is_none()
-                        {
-                            break;
-                        }
-                    }
+                    Ok(true) => match xterm::event::read() {
+                        Ok(ev) if ev == BREAK && cancel.cancel() => (),
+                        Ok(ev) if tx.send(ev).is_ok() => (),
+                        _ => break,
+                    },
                     Ok(false) => {}
                     Err(_) => break,
                 }
@@ -131,6 +135,25 @@ impl<'a> Interface<'a> {
         Ok(())
     }
 
+    /// Writes a dimmed hint after the buffer, without adding it to the buffer.
+    /// The hint is cut short to fit the line and is cleared on the next flush.
+    pub fn write_hint(&mut self, hint: &str) -> XResult<()> {
+        let width = term_width_nofail();
+        let column = self.buf.cursor_delta(self.buf.len(), width).0;
+        let hint = hint
+            .chars()
+            .take(width.saturating_sub(column + 1))
+            .collect::<String>();
+        queue!(
+            self.stdout,
+            SavePosition,
+            Print(hint.dimmed()),
+            RestorePosition
+        )?;
+        self.stdout.flush()?;
+        Ok(())
+    }
+
     pub fn read_until(&mut self, events: &[Event]) -> XResult<Event> {
         const NOMOD: KeyModifiers = KeyModifiers::empty();
         macro_rules! nomod {
@@ -396,6 +419,8 @@ impl fmt::Display for InputBuffer {
 pub struct CItem {
     pub matchstr: String,
     pub input_chpos: usize,
+    /// Written after the completion but not added to the input.
+    pub hint: Option<String>,
 }
 
 #[derive(Default)]
@@ -438,11 +463,15 @@ impl CompletionWriter {
         if let Some(CItem {
             matchstr,
             input_chpos,
+            hint,
         }) = completion
         {
             interface.truncate(*input_chpos);
             interface.write(matchstr);
             interface.flush_buffer()?;
+            if let Some(hint) = hint {
+                interface.write_hint(hint)?;
+            }
             self.input_line = interface.buffer();
         }
 
@@ -530,7 +559,6 @@ fn term_width_nofail() -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use colored::*;
 
     #[test]
     fn test_input_movement() {
@@ -620,7 +648,7 @@ mod tests {
 
         // setup terminal
         let (origcols, _origrows) = size()?;
-        let mut screen = Screen::new()?;
+        let mut screen = Screen::new(Default::default())?;
         let mut inputbuf = InputBuffer::new();
         let mut history = VecDeque::from(vec!["Hello".to_string(), "World".to_string()]);
         let mut input = screen.begin_interface_input(&mut inputbuf, &mut history)?;
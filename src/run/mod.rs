@@ -1,6 +1,11 @@
 #[cfg(feature = "racer-completion")]
 use crate::complete::code::{CodeCache, CodeCompleter};
-use crate::complete::{cmdr::TreeCompleter, modules::ModulesCompleter};
+use crate::complete::{
+    cmdr::{CustomArgCompleter, TreeCompleter},
+    files::FilesCompleter,
+    modules::ModulesCompleter,
+    stmts::StmtsCompleter,
+};
 use crate::prelude::*;
 use crossterm as xterm;
 use crossterm::event::Event;
@@ -36,7 +41,7 @@ pub fn terminal_width() -> Option<usize> {
 /// terminal width is greater than 120, the size limit is `max(80% * terminal-width, 120) - prompt`.
 ///
 /// This is the function used by the REPL main entry point.
-pub fn fmt_based_on_terminal_width<D>(repl: &Repl<Print, D>) -> FormattingConfig {
+pub fn fmt_based_on_terminal_width<D, S>(repl: &Repl<Print, D, S>) -> FormattingConfig {
     terminal_width()
         .map(|width| {
             let mut fmt = FormattingConfig::default();
@@ -57,14 +62,14 @@ pub fn fmt_based_on_terminal_width<D>(repl: &Repl<Print, D>) -> FormattingConfig
 /// don't require D: Send + 'static, instead this bound is only required on the creation of a
 /// RunCallbacks which is using the _async_ variant.
 #[allow(clippy::type_complexity)]
-enum Data<'a, D> {
+enum Data<'a, D, S> {
     Sync(
         &'a mut D,
-        Box<dyn FnMut(Repl<Evaluate, D>, &mut D) -> EvalResult<D>>,
+        Box<dyn FnMut(Repl<Evaluate, D, S>, &mut D) -> EvalResult<D, S>>,
     ),
     Async(
         Arc<Mutex<D>>,
-        Box<dyn FnMut(Repl<Evaluate, D>, &Arc<Mutex<D>>) -> EvalResult<D>>,
+        Box<dyn FnMut(Repl<Evaluate, D, S>, &Arc<Mutex<D>>) -> EvalResult<D, S>>,
     ),
 }
 
@@ -72,20 +77,25 @@ enum Data<'a, D> {
 ///
 /// These callbacks are mostly around setting up formatting and feeding evaluation results to the
 /// caller.
-pub struct RunCallbacks<'a, D, T, U, V> {
-    data: Data<'a, D>,
+pub struct RunCallbacks<'a, D, T, U, V, W, X, S = ()> {
+    data: Data<'a, D, S>,
     fmtrfn: Option<T>,
     resultfn: Option<U>,
     exitfn: Option<V>,
+    signalfn: Option<W>,
+    cmdresultfn: Option<X>,
 }
 
-impl<'a, D>
+impl<'a, D, S>
     RunCallbacks<
         'a,
         D,
-        fn(&Repl<Print, D>) -> FormattingConfig,
-        fn(usize, Kserd<'static>, &Repl<Read, D>),
-        fn(&mut ReplData<D>, &mut D),
+        fn(&Repl<Print, D, S>) -> FormattingConfig,
+        fn(usize, Kserd<'static>, &Repl<Read, D, S>),
+        fn(&mut ReplData<D, S>, &mut D),
+        fn(S, &mut ReplData<D, S>, &mut D),
+        fn(Kserd<'static>, &Repl<Read, D, S>),
+        S,
     >
 {
     /// New callback using a synchronous model of data ownership. (eg `eval`).
@@ -95,6 +105,8 @@ impl<'a, D>
             fmtrfn: None,
             resultfn: None,
             exitfn: None,
+            signalfn: None,
+            cmdresultfn: None,
         }
     }
 
@@ -102,6 +114,7 @@ impl<'a, D>
     pub fn new_async(app_data: Arc<Mutex<D>>) -> Self
     where
         D: Send + 'static,
+        S: Send + 'static,
     {
         Self {
             data: Data::Async(
@@ -111,20 +124,24 @@ impl<'a, D>
             fmtrfn: None,
             resultfn: None,
             exitfn: None,
+            signalfn: None,
+            cmdresultfn: None,
         }
     }
 }
 
-impl<'a, D, T, U, V> RunCallbacks<'a, D, T, U, V> {
+impl<'a, D, T, U, V, W, X, S> RunCallbacks<'a, D, T, U, V, W, X, S> {
     /// Specify code to be run which dictates the formatting configuration to use.
-    pub fn with_fmtrfn<F>(self, f: F) -> RunCallbacks<'a, D, F, U, V>
+    pub fn with_fmtrfn<F>(self, f: F) -> RunCallbacks<'a, D, F, U, V, W, X, S>
     where
-        F: FnMut(&Repl<Print, D>) -> FormattingConfig,
+        F: FnMut(&Repl<Print, D, S>) -> FormattingConfig,
     {
         let RunCallbacks {
             data,
             resultfn,
             exitfn,
+            signalfn,
+            cmdresultfn,
             ..
         } = self;
         RunCallbacks {
@@ -132,21 +149,26 @@ impl<'a, D, T, U, V> RunCallbacks<'a, D, T, U, V> {
             fmtrfn: Some(f),
             resultfn,
             exitfn,
+            signalfn,
+            cmdresultfn,
         }
     }
 
     /// Specify code to be run after evaluation has succeeded and a `Kserd` result is returned.
     ///
     /// The closure supplies the statement index `usize` and the result `Kserd`, along with the
-    /// `Repl`.
-    pub fn with_resultfn<F>(self, f: F) -> RunCallbacks<'a, D, T, F, V>
+    /// `Repl`. Values returned by commands are handed to
+    /// [`with_cmd_resultfn`](RunCallbacks::with_cmd_resultfn) instead.
+    pub fn with_resultfn<F>(self, f: F) -> RunCallbacks<'a, D, T, F, V, W, X, S>
     where
-        F: FnMut(usize, Kserd<'static>, &Repl<Read, D>),
+        F: FnMut(usize, Kserd<'static>, &Repl<Read, D, S>),
     {
         let RunCallbacks {
             data,
             fmtrfn,
             exitfn,
+            signalfn,
+            cmdresultfn,
             ..
         } = self;
         RunCallbacks {
@@ -154,20 +176,24 @@ impl<'a, D, T, U, V> RunCallbacks<'a, D, T, U, V> {
             fmtrfn,
             resultfn: Some(f),
             exitfn,
+            signalfn,
+            cmdresultfn,
         }
     }
 
     /// Specify code to be run after the exit signal is received.
     ///
     /// This can be used to clean up resources within [`ReplData`] or `D`.
-    pub fn with_exitfn<F>(self, f: F) -> RunCallbacks<'a, D, T, U, F>
+    pub fn with_exitfn<F>(self, f: F) -> RunCallbacks<'a, D, T, U, F, W, X, S>
     where
-        F: FnOnce(&ReplData<D>, &mut D),
+        F: FnOnce(&ReplData<D, S>, &mut D),
     {
         let RunCallbacks {
             data,
             fmtrfn,
             resultfn,
+            signalfn,
+            cmdresultfn,
             ..
         } = self;
         RunCallbacks {
@@ -175,32 +201,95 @@ impl<'a, D, T, U, V> RunCallbacks<'a, D, T, U, V> {
             fmtrfn,
             resultfn,
             exitfn: Some(f),
+            signalfn,
+            cmdresultfn,
+        }
+    }
+
+    /// Specify code to be run when a custom signal is received, such as one sent by
+    /// [`CommandResult::custom_signal`](crate::cmds::CommandResult::custom_signal).
+    ///
+    /// The closure supplies the signal payload, along with the [`ReplData`] and `D`.
+    pub fn with_signalfn<F>(self, f: F) -> RunCallbacks<'a, D, T, U, V, F, X, S>
+    where
+        F: FnMut(S, &mut ReplData<D, S>, &mut D),
+    {
+        let RunCallbacks {
+            data,
+            fmtrfn,
+            resultfn,
+            exitfn,
+            cmdresultfn,
+            ..
+        } = self;
+        RunCallbacks {
+            data,
+            fmtrfn,
+            resultfn,
+            exitfn,
+            signalfn: Some(f),
+            cmdresultfn,
+        }
+    }
+
+    /// Specify code to be run when a command returns a `Kserd` value, such as one returned by
+    /// [`CommandResult::value_fn`](crate::cmds::CommandResult::value_fn).
+    ///
+    /// The closure supplies the value, along with the `Repl`.
+    pub fn with_cmd_resultfn<F>(self, f: F) -> RunCallbacks<'a, D, T, U, V, W, F, S>
+    where
+        F: FnMut(Kserd<'static>, &Repl<Read, D, S>),
+    {
+        let RunCallbacks {
+            data,
+            fmtrfn,
+            resultfn,
+            exitfn,
+            signalfn,
+            ..
+        } = self;
+        RunCallbacks {
+            data,
+            fmtrfn,
+            resultfn,
+            exitfn,
+            signalfn,
+            cmdresultfn: Some(f),
         }
     }
 }
 
 /// Available with the `runnable` feature and when the REPL is in the `Read` state.
-impl<D> Repl<Read, D> {
+impl<D, S> Repl<Read, D, S> {
     /// Run the repl inside the terminal, consuming the repl. Returns the output of the REPL.
-    pub fn run<T, U, V>(self, run_callbacks: RunCallbacks<D, T, U, V>) -> io::Result<String>
+    pub fn run<T, U, V, W, X>(
+        self,
+        run_callbacks: RunCallbacks<D, T, U, V, W, X, S>,
+    ) -> io::Result<String>
     where
-        T: FnMut(&Repl<Print, D>) -> kserd::fmt::FormattingConfig,
-        U: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D>),
-        V: FnOnce(&mut ReplData<D>, &mut D),
+        T: FnMut(&Repl<Print, D, S>) -> kserd::fmt::FormattingConfig,
+        U: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D, S>),
+        V: FnOnce(&mut ReplData<D, S>, &mut D),
+        W: FnMut(S, &mut ReplData<D, S>, &mut D),
+        X: FnMut(kserd::Kserd<'static>, &Repl<Read, D, S>),
     {
-        run(self, run_callbacks, Screen::new).map_err(|e| map_xterm_err(e, "running REPL failed"))
+        let cancel = self.data.cancel_handle();
+        run(self, run_callbacks, move || Screen::new(cancel))
+            .map_err(|e| map_xterm_err(e, "running REPL failed"))
     }
 }
 
-fn run<D, FmtrFn, ResultFn, ExitFn>(
-    mut read: Repl<Read, D>,
-    mut runcb: RunCallbacks<D, FmtrFn, ResultFn, ExitFn>,
+fn run<D, S, FmtrFn, ResultFn, ExitFn, SignalFn, CmdResultFn>(
+    mut read: Repl<Read, D, S>,
+    mut runcb: RunCallbacks<D, FmtrFn, ResultFn, ExitFn, SignalFn, CmdResultFn, S>,
     screen_fn: impl FnOnce() -> io::Result<Screen>,
 ) -> xterm::Result<String>
 where
-    FmtrFn: FnMut(&Repl<Print, D>) -> kserd::fmt::FormattingConfig,
-    ResultFn: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D>),
-    ExitFn: FnOnce(&mut ReplData<D>, &mut D),
+    FmtrFn: FnMut(&Repl<Print, D, S>) -> kserd::fmt::FormattingConfig,
+    ResultFn: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D, S>),
+    ExitFn: FnOnce(&mut ReplData<D, S>, &mut D),
+    SignalFn: FnMut(S, &mut ReplData<D, S>, &mut D),
+    CmdResultFn: FnMut(kserd::Kserd<'static>, &Repl<Read, D, S>),
 {
     // set a custom panic handler to dump to a file
     // must be done as the screen captures the io streams and will
@@ -241,7 +330,8 @@ where
     let mut reevaluate: Option<String> = None;
 
     // must seed the history size, this is maintained as constant.
-    let mut history = std::collections::VecDeque::from(vec![String::default(); 100]); // 100 history
+    let mut history =
+        std::collections::VecDeque::from(vec![String::default(); read.data.history_size.max(1)]);
 
     let output = loop {
         let mut interface = screen.begin_interface_input(&mut inputbuf, &mut history)?;
@@ -276,10 +366,23 @@ where
 
                         break repl.output().to_owned();
                     }
-                    (repl, signal) => {
+                    (mut repl, signal) => {
                         let mut reeval = None;
-                        if let Signal::ReEvaluate(s) = signal {
-                            reeval = Some(s);
+                        match signal {
+                            Signal::ReEvaluate(s) => reeval = Some(s),
+                            Signal::Custom(payload) => {
+                                if let Some(signalfn) = &mut runcb.signalfn {
+                                    match &mut runcb.data {
+                                        Data::Sync(d, _) => signalfn(payload, &mut repl.data, d),
+                                        Data::Async(d, _) => signalfn(
+                                            payload,
+                                            &mut repl.data,
+                                            &mut d.lock().unwrap(),
+                                        ),
+                                    }
+                                }
+                            }
+                            _ => (),
                         }
                         read = repl;
                         reevaluate = reeval;
@@ -335,8 +438,8 @@ fn construct_crash_report(
 }
 
 /// Returns true if interrupt occurred.
-fn do_read<D>(
-    repl: &mut Repl<Read, D>,
+fn do_read<D, S>(
+    repl: &mut Repl<Read, D, S>,
     interface: &mut Interface,
     cache: &CacheWrapper,
 ) -> xterm::Result<bool> {
@@ -371,6 +474,9 @@ fn do_read<D>(
     let rdata = &repl.data;
     let treecmpltr = TreeCompleter::build(&rdata.cmdtree);
     let modscmpltr = ModulesCompleter::build(&rdata.cmdtree, rdata.mods_map());
+    let filescmpltr = FilesCompleter::build(&rdata.cmdtree, rdata.static_files());
+    let stmtscmpltr = StmtsCompleter::build(&rdata.cmdtree, rdata.current_src());
+    let argscmpltr = CustomArgCompleter::build(&rdata.cmdtree, &rdata.arg_completers);
     #[cfg(feature = "racer-completion")]
     let codecmpltr = CodeCompleter::build(rdata);
     let prompt = repl.prompt(true);
@@ -408,6 +514,9 @@ fn do_read<D>(
 
                 let tree_chpos = f(TreeCompleter::word_break(&line));
                 let mods_chpos = f(ModulesCompleter::word_break(&line));
+                let files_chpos = f(FilesCompleter::word_break(&line));
+                let stmts_chpos = f(StmtsCompleter::word_break(&line));
+                let args_chpos = f(CustomArgCompleter::word_break(&line));
                 #[cfg(feature = "racer-completion")]
                 let code_chpos = f(CodeCompleter::word_break(&line));
 
@@ -428,7 +537,10 @@ fn do_read<D>(
 
                 let completions = completions
                     .chain(complete_cmdtree(&treecmpltr, &line, tree_chpos))
-                    .chain(complete_mods(&modscmpltr, &line, mods_chpos));
+                    .chain(complete_words(modscmpltr.complete(&line), mods_chpos))
+                    .chain(complete_words(filescmpltr.complete(&line), files_chpos))
+                    .chain(complete_stmts(&stmtscmpltr, &line, stmts_chpos))
+                    .chain(complete_words(argscmpltr.complete(&line), args_chpos));
 
                 completion_writer.new_completions(completions);
             }
@@ -460,17 +572,30 @@ fn complete_cmdtree<'a>(
     tree.complete(line).map(move |x| CItem {
         matchstr: x.0.to_owned(),
         input_chpos: chpos,
+        hint: None,
     })
 }
 
-fn complete_mods<'a>(
-    mods: &'a ModulesCompleter,
-    line: &'a str,
+fn complete_words<'a, I: 'a + Iterator<Item = String>>(
+    words: I,
     chpos: usize,
 ) -> impl Iterator<Item = CItem> + 'a {
-    mods.complete(line).map(move |x| CItem {
+    words.map(move |x| CItem {
         matchstr: x,
         input_chpos: chpos,
+        hint: None,
+    })
+}
+
+fn complete_stmts<'a>(
+    stmts: &'a StmtsCompleter,
+    line: &'a str,
+    chpos: usize,
+) -> impl Iterator<Item = CItem> + 'a {
+    stmts.complete(line).map(move |x| CItem {
+        matchstr: x.0,
+        input_chpos: chpos,
+        hint: Some(format!(" -- {}", x.1)),
     })
 }
 
@@ -486,16 +611,18 @@ fn complete_code(
         .map(move |x| CItem {
             matchstr: x.matchstr,
             input_chpos: chpos,
+            hint: None,
         })
 }
 
-fn do_eval<D, FmtrFn, ResultFn, ExitFn>(
-    mut repl: Repl<Evaluate, D>,
-    runcb: &mut RunCallbacks<D, FmtrFn, ResultFn, ExitFn>,
-) -> (Repl<Read, D>, Signal)
+fn do_eval<D, S, FmtrFn, ResultFn, ExitFn, SignalFn, CmdResultFn>(
+    mut repl: Repl<Evaluate, D, S>,
+    runcb: &mut RunCallbacks<D, FmtrFn, ResultFn, ExitFn, SignalFn, CmdResultFn, S>,
+) -> (Repl<Read, D, S>, Signal<S>)
 where
-    FmtrFn: FnMut(&Repl<Print, D>) -> kserd::fmt::FormattingConfig,
-    ResultFn: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D>),
+    FmtrFn: FnMut(&Repl<Print, D, S>) -> kserd::fmt::FormattingConfig,
+    ResultFn: FnMut(usize, kserd::Kserd<'static>, &Repl<Read, D, S>),
+    CmdResultFn: FnMut(kserd::Kserd<'static>, &Repl<Read, D, S>),
 {
     let rx = repl.output_listen();
 
@@ -521,10 +648,18 @@ where
     let (mut read, signal) = {
         let (repl, signal) = (r.repl, r.signal);
         let (repl, result) = repl.print_with_formatting(fmt);
-        if let Some((idx, kserd)) = result {
-            if let Some(f) = &mut runcb.resultfn {
-                f(idx, kserd, &repl);
+        match result {
+            Some((Some(idx), kserd)) => {
+                if let Some(f) = &mut runcb.resultfn {
+                    f(idx, kserd, &repl);
+                }
+            }
+            Some((None, kserd)) => {
+                if let Some(f) = &mut runcb.cmdresultfn {
+                    f(kserd, &repl);
+                }
             }
+            None => (),
         }
         (repl, signal)
     };
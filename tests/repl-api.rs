@@ -8,7 +8,7 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-const BUILD_DIR_IDX: AtomicUsize = AtomicUsize::new(0);
+static BUILD_DIR_IDX: AtomicUsize = AtomicUsize::new(0);
 
 fn unqiue_build_dir() -> PathBuf {
     format!(
@@ -18,7 +18,7 @@ fn unqiue_build_dir() -> PathBuf {
     .into()
 }
 
-fn chg_compile_dir<T, U>(mut repl: Repl<T, U>) -> Repl<T, U> {
+fn chg_compile_dir<T, U, V>(mut repl: Repl<T, U, V>) -> Repl<T, U, V> {
     repl.data.with_compilation_dir(unqiue_build_dir()).unwrap();
     repl
 }
@@ -48,7 +48,7 @@ a
             assert_eq!(signal, Signal::None);
             let (repl, result_kserd) = repl.print();
             let expected_kserd = Kserd::new_str("Hello\nMultiline\nInput\n");
-            assert_eq!(result_kserd, Some((0, expected_kserd)));
+            assert_eq!(result_kserd, Some((Some(0), expected_kserd)));
             repl
         }
     };
@@ -70,8 +70,361 @@ a
             assert_eq!(signal, Signal::None);
             let (repl, result_kserd) = repl.print();
             let expected_kserd = Kserd::new_str("Hello\nWorld!");
-            assert_eq!(result_kserd, Some((1, expected_kserd)));
+            assert_eq!(result_kserd, Some((Some(1), expected_kserd)));
             repl
         }
     };
 }
+
+fn eval_line<D>(
+    mut repl: Repl<repl::Read, D>,
+    input: &str,
+    app_data: &mut D,
+) -> Repl<repl::Read, D> {
+    repl.line_input(input);
+    match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => {
+            let repl::EvalResult { repl, signal } = repl.eval(app_data);
+            assert_eq!(signal, Signal::None);
+            repl.print().0
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn persistent_and_transactional_mut_blocks() {
+    let mut repl = chg_compile_dir(repl!(String));
+    repl.data.with_transactional_mut();
+    let mut app_data = String::from("start");
+
+    // a failing transactional block is rolled back
+    repl = eval_line(repl, ":mut tx", &mut app_data);
+    repl = eval_line(
+        repl,
+        "app_data.push_str(\" changed\"); panic!(\"fail\")",
+        &mut app_data,
+    );
+    assert_eq!(app_data, "start");
+
+    // a persisted block can be replayed
+    repl = eval_line(repl, ":mut persist", &mut app_data);
+    repl = eval_line(repl, "app_data.push_str(\"!\")", &mut app_data);
+    assert_eq!(app_data, "start!");
+    assert_eq!(repl.data.current_src().stmts.len(), 0);
+
+    repl = eval_line(repl, ":mut replay 0", &mut app_data);
+    assert_eq!(app_data, "start!!");
+    assert!(!repl.data.linking().mutable);
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn time_and_bench_do_not_keep_input() {
+    let mut repl = chg_compile_dir(repl!());
+
+    repl = eval_line(repl, "let x = 2; x * 2", &mut ());
+    repl = eval_line(repl, ":time x + 1", &mut ());
+    assert!(repl.output().contains("[:time x + 1]: ("));
+
+    repl.line_input(":bench x + 1 10");
+    let (repl, value) = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => repl.eval(&mut ()).repl.print(),
+    };
+    let (_, value) = value.expect("bench returns a value");
+    let cntr = value.cntr().unwrap();
+    assert_eq!(cntr.get("iterations").and_then(|x| x.uint()), Some(10));
+    assert!(cntr.get_str("median").is_some());
+
+    assert_eq!(repl.data.current_src().stmts.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn bench_uses_repl_variables_named_like_its_locals() {
+    let mut repl = chg_compile_dir(repl!());
+
+    repl = eval_line(
+        repl,
+        "let start = 1u8; let times = 2u8; start + times",
+        &mut (),
+    );
+
+    repl.line_input(":bench start + times 5");
+    let (repl, value) = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => repl.eval(&mut ()).repl.print(),
+    };
+    let (_, value) = value.expect("bench returns a value");
+    let cntr = value.cntr().unwrap();
+    assert_eq!(cntr.get("iterations").and_then(|x| x.uint()), Some(5));
+    assert!(!repl.output().contains("error"));
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn alias_runs_commands_and_code() {
+    let mut repl = chg_compile_dir(repl!());
+
+    repl = eval_line(
+        repl,
+        ":alias two = :mod switch two ;; let y = 2; y + 1",
+        &mut (),
+    );
+    repl = eval_line(repl, ":alias six = :two ;; y * 3", &mut ());
+    repl = eval_line(repl, ":help", &mut ());
+    assert!(repl.output().contains("Aliases:\n\tsix -- :two ;; y * 3\n"));
+
+    repl = eval_line(repl, ":six", &mut ());
+    assert!(repl.output().ends_with("3\n6\n[two] papyrus=> "));
+    assert_eq!(repl.data.current_src().stmts.len(), 2);
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn startup_file_imports_and_items() {
+    let mut repl = chg_compile_dir(repl!());
+    let dir = unqiue_build_dir();
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let rc = dir.join(".papyrusrc.rs");
+    std::fs::write(
+        &rc,
+        "use std::collections::HashMap;\nfn two() -> i32 { 2 }\nlet x = two();\nfor _ in 0..x {}\nlet y = x * 2;\ny",
+    )
+    .unwrap();
+    repl.data.load_startup_file(&rc, &mut ()).unwrap();
+    assert_eq!(
+        repl.data.persistent_module_code().as_str(),
+        "use std::collections::HashMap;"
+    );
+    assert_eq!(repl.data.mods_map()[&PathBuf::from("lib")].items.len(), 1);
+    assert_eq!(repl.data.mods_map()[&PathBuf::from("lib")].stmts.len(), 1);
+
+    repl = eval_line(repl, ":mod switch other", &mut ());
+    repl = eval_line(repl, "HashMap::<i32, i32>::new().len()", &mut ());
+    assert!(repl.output().contains("usize = 0"));
+
+    std::fs::write(&rc, "use std::not_a_module::Thing;").unwrap();
+    assert!(repl.data.load_startup_file(&rc, &mut ()).is_err());
+    assert_eq!(
+        repl.data.persistent_module_code().as_str(),
+        "use std::collections::HashMap;"
+    );
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn watched_static_files_reload() {
+    let mut repl = chg_compile_dir(repl!());
+    let dir = PathBuf::from("target/testing/watch_static");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("watched.rs");
+    std::fs::write(&file, "pub fn one() -> i32 { 1 }").unwrap();
+
+    repl = eval_line(
+        repl,
+        ":static-files add target/testing/watch_static/watched.rs",
+        &mut (),
+    );
+    repl = eval_line(repl, ":static-files watch on", &mut ());
+    repl = eval_line(repl, "1 + 1", &mut ());
+    assert!(!repl.output().contains("reloaded static file"));
+
+    std::fs::write(&file, "pub fn one() -> i32 { 11 }").unwrap();
+    repl = eval_line(repl, "2 + 2", &mut ());
+    assert!(repl
+        .output()
+        .contains("reloaded static file `target/testing/watch_static/watched.rs`\n"));
+
+    repl = eval_line(repl, ":static-files watch off", &mut ());
+    std::fs::write(&file, "pub fn one() -> i32 { 1 }").unwrap();
+    repl = eval_line(repl, "3 + 3", &mut ());
+    assert_eq!(repl.output().matches("reloaded static file").count(), 1);
+}
+
+#[test]
+#[cfg(feature = "test-runnable")]
+fn persist_code_is_trial_compiled() {
+    let mut repl = chg_compile_dir(repl!());
+
+    repl = eval_line(repl, ":persist add use std::collections::HashMap;", &mut ());
+    repl = eval_line(repl, ":persist add use std::not_a_module::Thing;", &mut ());
+    assert!(repl
+        .output()
+        .contains("persistent module code rejected, compilation failed:"));
+    assert_eq!(
+        repl.data.persistent_module_code().as_str(),
+        "use std::collections::HashMap;"
+    );
+
+    repl = eval_line(repl, ":persist ls", &mut ());
+    assert!(repl
+        .output()
+        .contains("0: use std::collections::HashMap;\n"));
+
+    repl = eval_line(repl, ":env", &mut ());
+    assert!(repl
+        .output()
+        .contains("persistent module code:\n\tuse std::collections::HashMap;\n"));
+
+    repl = eval_line(repl, ":persist rm 1", &mut ());
+    assert!(repl.output().contains("index is outside of range"));
+    repl = eval_line(repl, ":persist rm 0", &mut ());
+    assert!(repl.data.persistent_module_code().is_empty());
+}
+
+#[test]
+fn command_value_is_printed_and_returned() {
+    use papyrus::cmds::CommandResult;
+    use papyrus::cmdtree::{Builder, BuilderChain};
+
+    let mut repl = chg_compile_dir(repl!(String));
+    repl.data
+        .with_cmdtree_builder(
+            Builder::new("papyrus")
+                .add_action("len", "length of app data", |_, _| {
+                    CommandResult::value_fn(|app_data: &mut String, _, _| {
+                        Kserd::new_num(app_data.len())
+                    })
+                })
+                .unwrap(),
+        )
+        .unwrap();
+
+    let mut app_data = String::from("Hello");
+
+    repl.line_input(":len");
+    let (repl, value) = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => {
+            let repl::EvalResult { repl, signal } = repl.eval(&mut app_data);
+            assert_eq!(signal, Signal::None);
+            repl.print()
+        }
+    };
+
+    assert_eq!(value, Some((None, Kserd::new_num(5usize))));
+    assert!(repl.output().contains("[:len]: 5\n"));
+}
+
+#[test]
+fn task_command_reports_progress_and_cancels() {
+    use papyrus::cmds::CommandResult;
+    use papyrus::cmdtree::{Builder, BuilderChain};
+    use std::sync::{Arc, Mutex};
+
+    let mut repl = chg_compile_dir(repl!());
+    repl.data
+        .with_cmdtree_builder(
+            Builder::new("papyrus")
+                .add_action("count", "count to three", |_, _| {
+                    CommandResult::task_fn(|task| {
+                        for i in 1..=3 {
+                            task.progress(&format!("counted {}", i));
+                        }
+                        String::from("finished counting")
+                    })
+                })
+                .add_action("forever", "run until cancelled", |_, _| {
+                    CommandResult::task_fn(|task| {
+                        while !task.is_cancelled() {
+                            std::thread::sleep(std::time::Duration::from_millis(5));
+                        }
+                        String::new()
+                    })
+                })
+                .unwrap(),
+        )
+        .unwrap();
+
+    repl = eval_line(repl, ":count", &mut ());
+    assert!(repl
+        .output()
+        .contains("counted 1\ncounted 2\ncounted 3\nfinished counting\n"));
+
+    let cancel = repl.data.cancel_handle();
+    assert!(!cancel.cancel());
+
+    repl.line_input(":forever");
+    let repl = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => repl,
+    };
+    let evaluating = repl.eval_async(&Arc::new(Mutex::new(())));
+    while !cancel.cancel() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let repl = evaluating.wait().repl.print().0;
+    assert!(repl.output().contains("task cancelled\n"));
+    assert!(!cancel.cancel());
+}
+
+#[test]
+fn task_command_locks_shared_app_data() {
+    use papyrus::cmds::CommandResult;
+    use papyrus::cmdtree::{Builder, BuilderChain};
+    use std::sync::{Arc, Mutex};
+
+    let mut repl = chg_compile_dir(repl!(String));
+    repl.data
+        .with_cmdtree_builder(
+            Builder::new("papyrus")
+                .add_action("shout", "make app_data uppercase", |_, _| {
+                    CommandResult::task_fn(|task| match task.app_data::<String>() {
+                        Some(app_data) => {
+                            let mut app_data = app_data.lock().unwrap();
+                            *app_data = app_data.to_uppercase();
+                            String::from("shouted")
+                        }
+                        None => String::from("no shared app data"),
+                    })
+                })
+                .unwrap(),
+        )
+        .unwrap();
+
+    let mut app_data = String::from("hello");
+    repl = eval_line(repl, ":shout", &mut app_data);
+    assert!(repl.output().contains("no shared app data\n"));
+    assert_eq!(app_data, "hello");
+
+    let app_data = Arc::new(Mutex::new(app_data));
+    repl.line_input(":shout");
+    let repl = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => repl.eval_async(&app_data).wait().repl.print().0,
+    };
+    assert!(repl.output().contains("shouted\n"));
+    assert_eq!(*app_data.lock().unwrap(), "HELLO");
+}
+
+#[test]
+fn custom_signal_is_returned() {
+    use papyrus::cmds::CommandResult;
+    use papyrus::cmdtree::{Builder, BuilderChain};
+
+    #[derive(Debug, PartialEq)]
+    struct ReloadAssets(String);
+
+    let mut repl = chg_compile_dir(repl!((), ReloadAssets));
+    repl.data
+        .with_cmdtree_builder(
+            Builder::new("papyrus")
+                .add_action("reload-assets", "reload the assets", |_, args| {
+                    CommandResult::custom_signal(ReloadAssets(args.join(" ")))
+                })
+                .unwrap(),
+        )
+        .unwrap();
+
+    repl.line_input(":reload-assets textures");
+    let signal = match repl.read() {
+        ReadResult::Read(_) => panic!("should be at Eval state!"),
+        ReadResult::Eval(repl) => repl.eval(&mut ()).signal,
+    };
+
+    assert_eq!(signal, Signal::Custom(ReloadAssets("textures".to_string())));
+}